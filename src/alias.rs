@@ -0,0 +1,223 @@
+//! Provides friendly names for commonly used SoundCore parameters.
+//!
+//! An [`Alias`](struct.Alias.html) maps a short name like `output` to a
+//! feature and parameter, and may give names to some of the parameter's
+//! values, e.g. `speakers` for `Device Control.SelectOutput = 1`. Aliases
+//! and their labels are defined by the `alias` and `labels` of parameters
+//! in the [`Catalog`](../catalog/struct.Catalog.html).
+//!
+//! Aliases are a layer over the `creative` map of a
+//! [`Configuration`](../struct.Configuration.html): use
+//! [`insert_alias`](fn.insert_alias.html) to add an aliased setting to the
+//! map, and [`extract_aliases`](fn.extract_aliases.html) to take the aliased
+//! settings back out of it.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+
+use crate::catalog::{Catalog, ParameterInfo};
+use crate::key::ItemKey;
+use crate::setting::Setting;
+use crate::soundcore::SoundCoreParamValue;
+
+/// A friendly name for a parameter.
+#[derive(Clone, Copy, Debug)]
+pub struct Alias<'a> {
+    /// The friendly name of the parameter
+    pub name: &'a str,
+    /// The catalog entry of the parameter, including its labels
+    pub info: &'a ParameterInfo,
+}
+
+impl<'a> Alias<'a> {
+    /// Lists the aliases defined by a catalog.
+    ///
+    /// A parameter described separately for several models has its alias
+    /// listed once, from its first entry.
+    pub fn all(catalog: &'a Catalog) -> impl Iterator<Item = Alias<'a>> {
+        catalog
+            .parameters
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, info)| {
+                let name = info.alias.as_deref()?;
+                let first = catalog.parameters[..index]
+                    .iter()
+                    .all(|earlier| earlier.alias.as_deref() != Some(name));
+                if first {
+                    Some(Alias { name, info })
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Finds an alias by name, ignoring case.
+    pub fn find(catalog: &'a Catalog, name: &str) -> Result<Alias<'a>, AliasError> {
+        Self::all(catalog)
+            .find(|alias| alias.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| AliasError::UnknownAlias {
+                name: name.to_owned(),
+                expected: Self::all(catalog)
+                    .map(|alias| alias.name.to_owned())
+                    .collect(),
+            })
+    }
+
+    /// Finds the alias for a parameter.
+    pub fn for_parameter(
+        catalog: &'a Catalog,
+        feature: &str,
+        parameter: &str,
+    ) -> Option<Alias<'a>> {
+        Self::all(catalog)
+            .find(|alias| alias.feature() == feature && alias.parameter() == parameter)
+    }
+
+    /// Gets the description of the feature that owns the parameter.
+    pub fn feature(&self) -> &'a str {
+        &self.info.feature
+    }
+
+    /// Gets the description of the parameter.
+    pub fn parameter(&self) -> &'a str {
+        &self.info.parameter
+    }
+
+    /// Gets the value with a label, ignoring case.
+    pub fn value(&self, label: &str) -> Option<SoundCoreParamValue> {
+        self.info
+            .labels
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(label.trim()))
+            .map(|(_, value)| value.to_param_value())
+    }
+
+    /// Gets the label for a value, if it has one.
+    pub fn label(&self, value: &SoundCoreParamValue) -> Option<&'a str> {
+        self.info.label(value)
+    }
+
+    /// Parses a label or a value for this parameter.
+    pub fn parse_value(&self, text: &str) -> Result<SoundCoreParamValue, AliasError> {
+        match self.value(text) {
+            Some(value) => Ok(value),
+            None => SoundCoreParamValue::from_str(text).map_err(|_| self.invalid_value(text)),
+        }
+    }
+
+    /// Parses a label, a value, or an adjustment for this parameter.
+    pub fn parse_setting(&self, text: &str) -> Result<Setting<SoundCoreParamValue>, AliasError> {
+        match self.value(text) {
+            Some(value) => Ok(Setting::Absolute(value)),
+            None => Setting::from_str(text).map_err(|_| self.invalid_value(text)),
+        }
+    }
+
+    fn invalid_value(&self, text: &str) -> AliasError {
+        AliasError::InvalidValue {
+            alias: self.name.to_owned(),
+            labels: self.info.labels.keys().cloned().collect(),
+            input: text.to_owned(),
+        }
+    }
+}
+
+/// Adds the setting for an alias to the `creative` map of a configuration.
+pub fn insert_alias(
+    catalog: &Catalog,
+    creative: &mut IndexMap<String, IndexMap<String, Setting<SoundCoreParamValue>>>,
+    name: &str,
+    value: Setting<SoundCoreParamValue>,
+) -> Result<(), AliasError> {
+    let alias = Alias::find(catalog, name)?;
+    creative
+        .entry(alias.feature().to_owned())
+        .or_default()
+        .insert(alias.parameter().to_owned(), value);
+    Ok(())
+}
+
+/// Removes the settings that have aliases from the `creative` map of a
+/// configuration.
+///
+/// Features and parameters may be keyed by description or as
+/// `#id description`. A parameter whose description is shared with another
+/// parameter of the same feature is left alone. Features left without any
+/// settings are removed as well.
+pub fn extract_aliases<'a>(
+    catalog: &'a Catalog,
+    creative: &mut IndexMap<String, IndexMap<String, Setting<SoundCoreParamValue>>>,
+) -> Vec<(Alias<'a>, Setting<SoundCoreParamValue>)> {
+    let mut result = Vec::new();
+    for alias in Alias::all(catalog) {
+        for (feature, parameters) in creative.iter_mut() {
+            if ItemKey::parse(feature).description() != Some(alias.feature()) {
+                continue;
+            }
+            let mut matching = parameters
+                .keys()
+                .enumerate()
+                .filter(|(_, key)| ItemKey::parse(key).description() == Some(alias.parameter()))
+                .map(|(index, _)| index);
+            if let (Some(index), None) = (matching.next(), matching.next()) {
+                if let Some((_, value)) = parameters.shift_remove_index(index) {
+                    result.push((alias, value));
+                    break;
+                }
+            }
+        }
+    }
+    creative.retain(|_, parameters| !parameters.is_empty());
+    result
+}
+
+/// Describes a failure to resolve an alias.
+#[derive(Debug)]
+pub enum AliasError {
+    /// No alias has the given name.
+    UnknownAlias {
+        /// The name
+        name: String,
+        /// The names of the known aliases
+        expected: Vec<String>,
+    },
+    /// The value is neither a label nor a valid parameter value.
+    InvalidValue {
+        /// The name of the alias the value was given for
+        alias: String,
+        /// The labels the alias accepts
+        labels: Vec<String>,
+        /// The value
+        input: String,
+    },
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliasError::UnknownAlias { name, expected } => write!(
+                f,
+                "unknown alias {:?}; expected one of {}",
+                name,
+                expected.join(", ")
+            ),
+            AliasError::InvalidValue {
+                alias,
+                labels,
+                input,
+            } => write!(
+                f,
+                "invalid value {:?} for {}; expected one of {} or a number",
+                input,
+                alias,
+                labels.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for AliasError {}
@@ -0,0 +1,329 @@
+//! Provides an async API for use with tokio, async-std, or other executors.
+//!
+//! The COM objects behind this crate can only be used on the thread that
+//! created them, and events are only delivered while that thread waits in a
+//! COM-aware way. A [`ComThread`](struct.ComThread.html) owns a dedicated
+//! single-threaded apartment that does this, and runs the COM work there.
+//! Its streams and futures are `Send`, so they can be awaited from any
+//! executor without blocking a thread per device.
+//!
+//! # Examples
+//!
+//! ```
+//! let thread = ComThread::new();
+//! let mut events = thread.volume_events(None);
+//! while let Some(event) = events.next().await {
+//!     println!("{:?}", event?);
+//! }
+//! ```
+
+use futures::channel::{mpsc, oneshot};
+use futures::future::{self, Future, FutureExt};
+use futures::{Stream, StreamExt};
+use windows::core::HSTRING;
+
+use std::error::Error;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::com::worker::ComWorker;
+use crate::ctsndcr::EventInfo;
+use crate::media::{DeviceEnumerator, DeviceNotification, VolumeNotification};
+use crate::soundcore::{
+    EventMask, SoundCore, SoundCoreContext, SoundCoreEvent, SoundCoreParamValue,
+};
+use crate::{get_endpoint, Configuration, DumpOptions, SetError, SetReport};
+
+/// Describes an error that occurred while using a [`ComThread`](struct.ComThread.html).
+#[derive(Debug)]
+pub enum ComThreadError {
+    /// The operation failed on the COM thread.
+    ///
+    /// Errors cannot be moved between threads, so only the message is kept.
+    Failed(String),
+    /// Applying a configuration failed after other devices were already
+    /// enabled or disabled.
+    ///
+    /// This is a [`SetError`](../struct.SetError.html) with the cause
+    /// replaced by its message.
+    Set {
+        /// The changes made before the failure
+        report: SetReport,
+        /// The message of the cause of the failure
+        message: String,
+    },
+    /// The COM thread stopped before the operation completed.
+    Stopped,
+}
+
+impl fmt::Display for ComThreadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ComThreadError::Failed(ref message) => write!(f, "{}", message),
+            ComThreadError::Set { ref message, .. } => write!(f, "{}", message),
+            ComThreadError::Stopped => write!(f, "The COM thread stopped"),
+        }
+    }
+}
+
+impl Error for ComThreadError {}
+
+impl From<Box<dyn Error>> for ComThreadError {
+    fn from(err: Box<dyn Error>) -> ComThreadError {
+        match err.downcast::<SetError>() {
+            Ok(err) => ComThreadError::Set {
+                report: err.report,
+                message: err.error.to_string(),
+            },
+            Err(err) => ComThreadError::Failed(err.to_string()),
+        }
+    }
+}
+
+impl From<windows::core::Error> for ComThreadError {
+    fn from(err: windows::core::Error) -> ComThreadError {
+        ComThreadError::Failed(err.to_string())
+    }
+}
+
+/// Describes an event produced through the SoundCore API.
+///
+/// This is a [`SoundCoreEvent`](../soundcore/enum.SoundCoreEvent.html) with
+/// the features and parameters replaced by their ids and descriptions, so it
+/// can be sent to another thread.
+#[derive(Clone, Debug)]
+pub enum SoundCoreNotification {
+    /// An event occurred that could not be translated.
+    Unknown(EventInfo),
+    /// The device switched to another context
+    ContextChange {
+        /// The original event information
+        info: EventInfo,
+        /// The context the device is now operating in
+        context: SoundCoreContext,
+    },
+    /// An event of an unrecognized type referred to a feature
+    FeatureEvent {
+        /// The original event information
+        info: EventInfo,
+        /// The id of the feature
+        feature_id: u32,
+        /// The description of the feature
+        feature: String,
+        /// The id and description of the parameter, if it exists
+        parameter: Option<(u32, String)>,
+    },
+    /// A parameter value has changed
+    ParamChange {
+        /// The id of the feature
+        feature_id: u32,
+        /// The description of the feature
+        feature: String,
+        /// The id of the parameter
+        parameter_id: u32,
+        /// The description of the parameter
+        parameter: String,
+        /// The value before the change, if it has been seen before
+        old: Option<SoundCoreParamValue>,
+        /// The value when the change was reported
+        new: SoundCoreParamValue,
+    },
+}
+
+impl From<SoundCoreEvent> for SoundCoreNotification {
+    fn from(event: SoundCoreEvent) -> Self {
+        match event {
+            SoundCoreEvent::Unknown(info) => SoundCoreNotification::Unknown(info),
+            SoundCoreEvent::ContextChange { info, context } => {
+                SoundCoreNotification::ContextChange { info, context }
+            }
+            SoundCoreEvent::FeatureEvent {
+                info,
+                feature,
+                parameter,
+            } => SoundCoreNotification::FeatureEvent {
+                info,
+                feature_id: feature.id,
+                feature: feature.description,
+                parameter: parameter.map(|parameter| (parameter.id, parameter.description)),
+            },
+            SoundCoreEvent::ParamChange {
+                feature,
+                parameter,
+                old,
+                new,
+            } => SoundCoreNotification::ParamChange {
+                feature_id: feature.id,
+                feature: feature.description,
+                parameter_id: parameter.id,
+                parameter: parameter.description,
+                old,
+                new,
+            },
+        }
+    }
+}
+
+/// A stream of events received on a [`ComThread`](struct.ComThread.html).
+///
+/// Dropping the stream unregisters from the events.
+pub struct EventStream<T> {
+    events: mpsc::UnboundedReceiver<T>,
+    // dropping this stops the task on the COM thread
+    _cancel: oneshot::Sender<()>,
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl<T> fmt::Debug for EventStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventStream").finish()
+    }
+}
+
+/// Runs COM work on a dedicated single-threaded apartment.
+///
+/// Handles can be cloned and shared between threads. The thread stops once
+/// every handle, stream, and pending operation is dropped.
+///
+/// `device_id` is handled as in the rest of the crate: if it is `None`, the
+/// system default output device is used.
+#[derive(Clone)]
+pub struct ComThread {
+    worker: ComWorker,
+}
+
+impl ComThread {
+    /// Starts a COM thread.
+    pub fn new() -> Self {
+        Self {
+            worker: ComWorker::start(),
+        }
+    }
+
+    /// Runs a function on the COM thread, and gets its result.
+    pub(crate) fn call<F, T>(&self, f: F) -> impl Future<Output = Result<T, ComThreadError>>
+    where
+        F: FnOnce() -> Result<T, Box<dyn Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        // if the thread stopped, the sender is dropped with the job
+        self.worker.spawn(move || {
+            let _ = sender.send(f().map_err(ComThreadError::from));
+            future::ready(())
+        });
+        receiver.map(|result| result.unwrap_or(Err(ComThreadError::Stopped)))
+    }
+
+    /// Forwards the items of a stream created on the COM thread.
+    fn stream<F, S, T>(&self, create: F) -> EventStream<Result<T, ComThreadError>>
+    where
+        F: FnOnce() -> Result<S, Box<dyn Error>> + Send + 'static,
+        S: Stream<Item = Result<T, ComThreadError>> + Unpin + 'static,
+        T: Send + 'static,
+    {
+        let (sender, events) = mpsc::unbounded();
+        let stopped = sender.clone();
+        let (cancel, cancelled) = oneshot::channel::<()>();
+        let spawned = self.worker.spawn(move || async move {
+            let stream = match create() {
+                Ok(stream) => stream,
+                Err(error) => {
+                    let _ = sender.unbounded_send(Err(error.into()));
+                    return;
+                }
+            };
+            let forward = stream.map(Ok).forward(sender);
+            // stop when the events end, the receiver is dropped, or the
+            // stream is dropped while no events arrive
+            future::select(forward, cancelled).await;
+        });
+        if !spawned {
+            let _ = stopped.unbounded_send(Err(ComThreadError::Stopped));
+        }
+        EventStream {
+            events,
+            _cancel: cancel,
+        }
+    }
+
+    /// Gets a stream of the SoundCore events of a device.
+    ///
+    /// See [`SoundCore::events_with_mask`](../soundcore/struct.SoundCore.html#method.events_with_mask).
+    pub fn sound_core_events(
+        &self,
+        device_id: Option<String>,
+        mask: EventMask,
+    ) -> EventStream<Result<SoundCoreNotification, ComThreadError>> {
+        self.stream(move || {
+            let endpoint = get_endpoint(device_id.map(HSTRING::from).as_ref())?;
+            let core = SoundCore::for_device(&endpoint.clsid()?, &endpoint.id()?)?;
+            Ok(core.event_stream(mask)?.map(|event| {
+                event
+                    .map(SoundCoreNotification::from)
+                    .map_err(ComThreadError::from)
+            }))
+        })
+    }
+
+    /// Gets a stream of the volume and mute changes of a device.
+    pub fn volume_events(
+        &self,
+        device_id: Option<String>,
+    ) -> EventStream<Result<VolumeNotification, ComThreadError>> {
+        self.stream(move || {
+            let endpoint = get_endpoint(device_id.map(HSTRING::from).as_ref())?;
+            Ok(endpoint.event_stream()?.map(Ok))
+        })
+    }
+
+    /// Gets a stream of changes to the audio devices of the system.
+    pub fn device_events(&self) -> EventStream<Result<DeviceNotification, ComThreadError>> {
+        self.stream(|| Ok(DeviceEnumerator::new()?.event_stream()?.map(Ok)))
+    }
+
+    /// Applies a configuration to a device.
+    ///
+    /// See [`set`](../fn.set.html). If it fails after other devices were
+    /// enabled or disabled, the error is a `ComThreadError::Set` that reports
+    /// those changes.
+    pub fn set(
+        &self,
+        device_id: Option<String>,
+        configuration: Configuration,
+        mute: bool,
+    ) -> impl Future<Output = Result<SetReport, ComThreadError>> {
+        self.call(move || crate::set(device_id.map(HSTRING::from).as_ref(), &configuration, mute))
+    }
+
+    /// Captures a snapshot of a device's configuration.
+    ///
+    /// See [`dump_with_options`](../fn.dump_with_options.html).
+    pub fn dump(
+        &self,
+        device_id: Option<String>,
+        options: DumpOptions,
+    ) -> impl Future<Output = Result<Configuration, ComThreadError>> {
+        self.call(move || crate::dump_with_options(device_id.map(HSTRING::from).as_ref(), &options))
+    }
+}
+
+impl Default for ComThread {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ComThread {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ComThread").finish()
+    }
+}
@@ -0,0 +1,197 @@
+//! Describes known Sound Blaster models and their parameters.
+//!
+//! The built-in catalog is loaded from a TOML document embedded in this
+//! crate. It gives parameters human-readable descriptions, units, and
+//! labels for their values, and records when a parameter only takes effect
+//! if another parameter has a certain value.
+
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::soundcore::SoundCoreParamValue;
+
+const BUILTIN: &str = include_str!("catalog.toml");
+
+/// A collection of known models and parameters.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Catalog {
+    /// The known models
+    #[serde(default)]
+    pub models: Vec<Model>,
+    /// The known parameters
+    #[serde(default)]
+    pub parameters: Vec<ParameterInfo>,
+}
+
+/// Describes a model of sound device.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Model {
+    /// A short name for the model, e.g. `AE-5`
+    pub name: String,
+    /// The names of the audio interfaces that identify this model
+    pub interfaces: Vec<String>,
+}
+
+/// Describes a parameter of a feature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ParameterInfo {
+    /// The description of the feature, as reported by the device
+    pub feature: String,
+    /// The description of the parameter, as reported by the device
+    pub parameter: String,
+    /// The models that have this parameter, or empty for all models
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+    /// A friendly name for the parameter, e.g. `output`
+    ///
+    /// See [`Alias`](../alias/struct.Alias.html).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// A human-readable description of the parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The unit of the value, e.g. `dB`, `%`, or `Hz`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Names for some of the values of the parameter
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub labels: IndexMap<String, CatalogValue>,
+    /// Other parameters this parameter depends on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<Requirement>,
+}
+
+/// Describes a parameter value that another parameter depends on.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Requirement {
+    /// The description of the feature of the required parameter
+    pub feature: String,
+    /// The description of the required parameter
+    pub parameter: String,
+    /// The values of the required parameter that are compatible
+    pub values: Vec<CatalogValue>,
+    /// Explains the requirement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A parameter value in the catalog.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum CatalogValue {
+    /// A boolean value
+    Bool(bool),
+    /// A numeric value of any type
+    Number(f64),
+}
+
+impl CatalogValue {
+    /// Checks whether a parameter value is equal to this value.
+    ///
+    /// Numbers are compared by value, regardless of their types.
+    pub fn matches(&self, value: &SoundCoreParamValue) -> bool {
+        match (*self, value) {
+            (CatalogValue::Bool(a), &SoundCoreParamValue::Bool(b)) => a == b,
+            (CatalogValue::Number(a), &SoundCoreParamValue::Float(b)) => a == f64::from(b),
+            (CatalogValue::Number(a), &SoundCoreParamValue::U32(b)) => a == f64::from(b),
+            (CatalogValue::Number(a), &SoundCoreParamValue::I32(b)) => a == f64::from(b),
+            _ => false,
+        }
+    }
+
+    /// Converts this value to a parameter value.
+    ///
+    /// Whole numbers become integers and other numbers become floats; they
+    /// are converted to the type of the parameter when they are applied.
+    pub fn to_param_value(self) -> SoundCoreParamValue {
+        match self {
+            CatalogValue::Bool(b) => SoundCoreParamValue::Bool(b),
+            CatalogValue::Number(n)
+                if n.fract() == 0.0 && (0.0..=f64::from(u32::MAX)).contains(&n) =>
+            {
+                SoundCoreParamValue::U32(n as u32)
+            }
+            CatalogValue::Number(n) if n.fract() == 0.0 && f64::from(i32::MIN) <= n && n < 0.0 => {
+                SoundCoreParamValue::I32(n as i32)
+            }
+            CatalogValue::Number(n) => SoundCoreParamValue::Float(n as f32),
+        }
+    }
+}
+
+impl fmt::Display for CatalogValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CatalogValue::Bool(b) => b.fmt(f),
+            CatalogValue::Number(n) => n.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values: Vec<_> = self.values.iter().map(|v| v.to_string()).collect();
+        write!(
+            f,
+            "{}.{} = {}",
+            self.feature,
+            self.parameter,
+            values.join(" or ")
+        )
+    }
+}
+
+impl Catalog {
+    /// Loads the catalog built into this crate.
+    pub fn builtin() -> Catalog {
+        Catalog::from_str(BUILTIN).expect("built-in catalog is invalid")
+    }
+
+    /// Finds the model with an audio interface name.
+    pub fn model_for_interface(&self, interface: &str) -> Option<&Model> {
+        self.models
+            .iter()
+            .find(|model| model.interfaces.iter().any(|name| name == interface))
+    }
+
+    /// Finds information about a parameter.
+    ///
+    /// If `model` is `None`, only parameters common to all models are found.
+    pub fn parameter(
+        &self,
+        model: Option<&Model>,
+        feature: &str,
+        parameter: &str,
+    ) -> Option<&ParameterInfo> {
+        self.parameters.iter().find(|info| {
+            info.feature == feature && info.parameter == parameter && info.applies_to(model)
+        })
+    }
+}
+
+impl FromStr for Catalog {
+    type Err = toml::de::Error;
+
+    /// Parses a catalog from a TOML document.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s)
+    }
+}
+
+impl ParameterInfo {
+    /// Checks whether this parameter exists on a model.
+    pub fn applies_to(&self, model: Option<&Model>) -> bool {
+        self.models.is_empty() || model.map_or(false, |model| self.models.contains(&model.name))
+    }
+
+    /// Gets the label for a value, if it has one.
+    pub fn label(&self, value: &SoundCoreParamValue) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(_, labelled)| labelled.matches(value))
+            .map(|(label, _)| &label[..])
+    }
+}
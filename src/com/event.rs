@@ -1,16 +1,20 @@
 use futures::task::{self, ArcWake};
 use futures::Stream;
-use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Foundation::{CloseHandle, HANDLE, RPC_S_CALLPENDING};
 use windows::Win32::System::Com::{CoWaitForMultipleObjects, CWMO_DISPATCH_CALLS};
 use windows::Win32::System::Threading::{CreateEventW, SetEvent};
 use windows::Win32::System::WindowsProgramming::INFINITE;
 
+use std::fmt;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-struct ComWaker {
+pub(crate) struct ComWaker {
     ready_event: HANDLE,
+    cancelled: AtomicBool,
 }
 
 impl ArcWake for ComWaker {
@@ -33,20 +37,62 @@ unsafe impl Sync for ComWaker {}
 impl ComWaker {
     pub fn new() -> Self {
         let ready_event = unsafe { CreateEventW(None, false, false, None).unwrap() };
-        Self { ready_event }
+        Self {
+            ready_event,
+            cancelled: AtomicBool::new(false),
+        }
     }
 
-    pub fn sleep(&self) {
-        unsafe {
-            CoWaitForMultipleObjects(CWMO_DISPATCH_CALLS.0 as u32, INFINITE, &[self.ready_event])
-                .expect("failed to wait for wake");
+    /// Dispatches COM calls until woken or until `timeout` milliseconds
+    /// have passed.
+    ///
+    /// Returns `false` if the timeout passed.
+    pub fn sleep(&self, timeout: u32) -> bool {
+        let result = unsafe {
+            CoWaitForMultipleObjects(CWMO_DISPATCH_CALLS.0 as u32, timeout, &[self.ready_event])
+        };
+        match result {
+            Ok(_) => true,
+            Err(error) if error.code() == RPC_S_CALLPENDING => false,
+            Err(error) => panic!("failed to wait for wake: {}", error),
         }
     }
 }
 
+/// Stops an event iterator from another thread.
+///
+/// Cancelling wakes the iterator if it is waiting for an event. The iterator
+/// then ends, and unregisters from the events on its own thread.
+#[derive(Clone)]
+pub struct CancelHandle {
+    waker: Arc<ComWaker>,
+}
+
+impl CancelHandle {
+    /// Cancels the iterator.
+    pub fn cancel(&self) {
+        self.waker.cancelled.store(true, Ordering::SeqCst);
+        task::ArcWake::wake_by_ref(&self.waker);
+    }
+
+    /// Checks whether the iterator was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.waker.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancelHandle")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
 pub struct ComEventIterator<S> {
     waker: Arc<ComWaker>,
-    inner: S,
+    /// The stream, until it ends or the iterator is cancelled
+    inner: Option<S>,
 }
 
 impl<S, I> ComEventIterator<S>
@@ -56,7 +102,78 @@ where
     pub fn new(stream: S) -> Self {
         ComEventIterator {
             waker: Arc::new(ComWaker::new()),
-            inner: stream,
+            inner: Some(stream),
+        }
+    }
+
+    /// Gets a handle that cancels the iterator from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            waker: self.waker.clone(),
+        }
+    }
+
+    /// Gets the event that is set when the iterator should be polled again.
+    ///
+    /// This is an auto-reset event that remains owned by the iterator.
+    pub fn event_handle(&self) -> HANDLE {
+        self.waker.ready_event
+    }
+}
+
+impl<S, I> ComEventIterator<S>
+where
+    S: Stream<Item = I> + Unpin,
+{
+    fn poll(&mut self) -> Poll<Option<I>> {
+        if self.waker.cancelled.load(Ordering::SeqCst) {
+            // drop the stream on this thread to unregister it
+            self.inner = None;
+        }
+        let inner = match self.inner {
+            Some(ref mut inner) => inner,
+            None => return Poll::Ready(None),
+        };
+        let waker = task::waker_ref(&self.waker);
+        let context = &mut Context::from_waker(&*waker);
+        let result = Pin::new(inner).poll_next(context);
+        if let Poll::Ready(None) = result {
+            self.inner = None;
+        }
+        result
+    }
+
+    /// Gets the next event if one is available, without blocking.
+    ///
+    /// Pending COM calls, which deliver events, are dispatched first.
+    /// Returns `Poll::Pending` if there is no event yet, and
+    /// `Poll::Ready(None)` if the iterator has ended.
+    pub fn try_next(&mut self) -> Poll<Option<I>> {
+        self.next_timeout(Duration::ZERO)
+    }
+
+    /// Waits up to `timeout` for the next event.
+    ///
+    /// Returns `Poll::Pending` if the timeout passed, and
+    /// `Poll::Ready(None)` if the iterator has ended. A timeout too large to
+    /// be represented, such as `Duration::MAX`, waits indefinitely.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Poll<Option<I>> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            if let Poll::Ready(item) = self.poll() {
+                return Poll::Ready(item);
+            }
+            let remaining = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    // INFINITE is u32::MAX, so stay below it
+                    remaining.as_millis().min(u128::from(INFINITE - 1)) as u32
+                }
+                None => INFINITE,
+            };
+            if !self.waker.sleep(remaining) {
+                return Poll::Pending;
+            }
         }
     }
 }
@@ -68,13 +185,12 @@ where
     type Item = I;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let waker = task::waker_ref(&self.waker);
-        let context = &mut Context::from_waker(&*waker);
         loop {
-            match Pin::new(&mut self.inner).poll_next(context) {
-                Poll::Ready(Some(item)) => break Some(item),
-                Poll::Ready(None) => break None,
-                Poll::Pending => self.waker.sleep(),
+            match self.poll() {
+                Poll::Ready(item) => break item,
+                Poll::Pending => {
+                    self.waker.sleep(INFINITE);
+                }
             };
         }
     }
@@ -1,4 +1,5 @@
 pub mod event;
+pub(crate) mod worker;
 
 use std::fmt;
 use std::ops::Deref;
@@ -0,0 +1,86 @@
+use futures::channel::mpsc;
+use futures::task;
+use futures::{Future, StreamExt};
+use tracing::{debug, error};
+use windows::Win32::System::WindowsProgramming::INFINITE;
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+
+use super::event::ComWaker;
+use super::ComScope;
+
+/// A task that runs on the worker thread, and may hold COM objects.
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// Creates a task on the worker thread.
+type Job = Box<dyn FnOnce() -> Task + Send>;
+
+/// Runs tasks on a dedicated single-threaded apartment.
+///
+/// The thread dispatches COM calls while waiting, so event callbacks
+/// registered by its tasks are delivered. It stops once every handle is
+/// dropped and its tasks have finished.
+#[derive(Clone)]
+pub(crate) struct ComWorker {
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl ComWorker {
+    pub fn start() -> Self {
+        let (jobs, receiver) = mpsc::unbounded();
+        thread::Builder::new()
+            .name("sbz-switch COM".to_owned())
+            .spawn(move || run(receiver))
+            .expect("failed to start COM thread");
+        Self { jobs }
+    }
+
+    /// Runs a task on the worker thread.
+    ///
+    /// `create` is called on the worker thread, so the task it returns does
+    /// not need to be `Send`. Returns `false` if the thread has stopped.
+    pub fn spawn<F, T>(&self, create: F) -> bool
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Future<Output = ()> + 'static,
+    {
+        self.jobs
+            .unbounded_send(Box::new(move || Box::pin(create()) as Task))
+            .is_ok()
+    }
+}
+
+fn run(mut jobs: mpsc::UnboundedReceiver<Job>) {
+    let _scope = match ComScope::begin() {
+        Ok(scope) => scope,
+        Err(error) => {
+            error!(error = %error, "Could not initialize COM thread");
+            return;
+        }
+    };
+    debug!("Started COM thread");
+    let waker = Arc::new(ComWaker::new());
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut accepting = true;
+    loop {
+        let waker_ref = task::waker_ref(&waker);
+        let context = &mut Context::from_waker(&waker_ref);
+        while accepting {
+            match jobs.poll_next_unpin(context) {
+                Poll::Ready(Some(job)) => tasks.push(job()),
+                Poll::Ready(None) => accepting = false,
+                Poll::Pending => break,
+            }
+        }
+        // every task is polled after any wake, which is fine for a few tasks
+        tasks.retain_mut(|task| task.as_mut().poll(context).is_pending());
+        if !accepting && tasks.is_empty() {
+            break;
+        }
+        waker.sleep(INFINITE);
+    }
+    debug!("Stopped COM thread");
+}
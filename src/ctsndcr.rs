@@ -8,17 +8,13 @@
 #![allow(unknown_lints)]
 #![allow(clippy::clippy::unreadable_literal)]
 
-use std::sync::Mutex;
+use std::sync::Arc;
 
-use futures::channel::mpsc::UnboundedSender;
-use futures::executor;
-use futures::SinkExt;
 use windows::core::implement;
 use windows::core::interface;
 use windows::core::IUnknown;
 use windows::core::IUnknown_Vtbl;
 use windows::core::HRESULT;
-use windows::Win32::Foundation::E_ABORT;
 use windows::Win32::Foundation::S_OK;
 
 #[interface("6111e7c4-3ea4-47ed-b074-c638875282c4")]
@@ -130,22 +126,18 @@ pub struct EventInfo {
 
 #[implement(ICallback)]
 pub(crate) struct Callback {
-    sender: Mutex<UnboundedSender<EventInfo>>,
+    handler: Arc<dyn Fn(EventInfo) + Send + Sync>,
 }
 
 impl Callback {
-    pub fn new(sender: UnboundedSender<EventInfo>) -> Self {
-        Self {
-            sender: Mutex::new(sender),
-        }
+    pub fn new(handler: Arc<dyn Fn(EventInfo) + Send + Sync>) -> Self {
+        Self { handler }
     }
 }
 
 impl ICallback_Impl for Callback {
     unsafe fn EventCallback(&self, event_info: EventInfo) -> HRESULT {
-        match executor::block_on(self.sender.lock().unwrap().send(event_info)) {
-            Ok(()) => S_OK,
-            Err(_) => E_ABORT,
-        }
+        (self.handler)(event_info);
+        S_OK
     }
 }
@@ -0,0 +1,402 @@
+//! Coalesces bursts of events, such as those produced by dragging a slider.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::stream::Fuse;
+use futures::{Stream, StreamExt};
+
+/// An event that can replace earlier events of the same kind.
+pub(crate) trait Coalesce: Sized {
+    /// Identifies the events that replace each other.
+    type Key: PartialEq;
+
+    /// Gets the key of the event, or `None` if it should never be coalesced.
+    fn coalesce_key(&self) -> Option<Self::Key>;
+
+    /// Combines the event with a later one with the same key.
+    ///
+    /// Returns `None` if the events cancel out, such as a value changing and
+    /// then changing back.
+    fn coalesce(self, later: Self) -> Option<Self> {
+        Some(later)
+    }
+}
+
+impl<T, E> Coalesce for Result<T, E>
+where
+    T: Coalesce,
+{
+    type Key = T::Key;
+
+    fn coalesce_key(&self) -> Option<Self::Key> {
+        self.as_ref().ok().and_then(Coalesce::coalesce_key)
+    }
+
+    fn coalesce(self, later: Self) -> Option<Self> {
+        match (self, later) {
+            (Ok(earlier), Ok(later)) => earlier.coalesce(later).map(Ok),
+            (_, later) => Some(later),
+        }
+    }
+}
+
+/// Adds [`debounce`](#method.debounce) to streams of events.
+pub(crate) trait DebounceExt: Stream + Sized
+where
+    Self::Item: Coalesce,
+{
+    /// Holds back each event until no event with the same key has followed
+    /// it for `window`, and then produces a single event for all of them.
+    ///
+    /// Events without a key are not held back, but the events held back
+    /// before them are produced first, so events are never reordered. A zero
+    /// `window` produces every event immediately.
+    fn debounce(self, window: Duration) -> Debounce<Self> {
+        Debounce {
+            inner: self.fuse(),
+            window,
+            pending: Vec::new(),
+            ready: VecDeque::new(),
+            timer: Timer::default(),
+        }
+    }
+}
+
+impl<S> DebounceExt for S
+where
+    S: Stream,
+    S::Item: Coalesce,
+{
+}
+
+struct Pending<T: Coalesce> {
+    key: T::Key,
+    event: Option<T>,
+    deadline: Instant,
+}
+
+/// A stream that coalesces events within a window.
+///
+/// See [`DebounceExt::debounce`](trait.DebounceExt.html#method.debounce).
+pub(crate) struct Debounce<S>
+where
+    S: Stream,
+    S::Item: Coalesce,
+{
+    inner: Fuse<S>,
+    window: Duration,
+    /// Events being held back, in the order they first arrived
+    pending: Vec<Pending<S::Item>>,
+    ready: VecDeque<S::Item>,
+    /// Wakes the stream when the first pending event is due
+    timer: Timer,
+}
+
+impl<S> Debounce<S>
+where
+    S: Stream,
+    S::Item: Coalesce,
+{
+    fn push(&mut self, event: S::Item) {
+        let key = match event.coalesce_key() {
+            Some(key) if self.window > Duration::ZERO => key,
+            _ => {
+                // keep the order of the events
+                self.release(true);
+                self.ready.push_back(event);
+                return;
+            }
+        };
+        let deadline = Instant::now() + self.window;
+        match self.pending.iter_mut().find(|pending| pending.key == key) {
+            Some(pending) => {
+                pending.event = match pending.event.take() {
+                    Some(earlier) => earlier.coalesce(event),
+                    None => Some(event),
+                };
+                pending.deadline = deadline;
+            }
+            None => self.pending.push(Pending {
+                key,
+                event: Some(event),
+                deadline,
+            }),
+        }
+    }
+
+    fn release(&mut self, all: bool) {
+        let now = Instant::now();
+        let mut i = 0;
+        while i < self.pending.len() {
+            if all || self.pending[i].deadline <= now {
+                if let Some(event) = self.pending.remove(i).event {
+                    self.ready.push_back(event);
+                }
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn arm_timer(&mut self, cx: &mut Context) {
+        if let Some(deadline) = self.pending.iter().map(|pending| pending.deadline).min() {
+            self.timer.wake_at(deadline, cx.waker());
+        }
+    }
+}
+
+/// Wakes a task at a deadline.
+///
+/// A single thread waits for the deadline. It is started when the timer is
+/// first armed and exits when the timer is dropped.
+#[derive(Default)]
+pub(crate) struct Timer {
+    shared: Option<Arc<TimerShared>>,
+}
+
+#[derive(Default)]
+struct TimerShared {
+    state: Mutex<TimerState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct TimerState {
+    deadline: Option<Instant>,
+    waker: Option<Waker>,
+    dropped: bool,
+}
+
+impl Timer {
+    /// Wakes `waker` once `deadline` has passed, replacing the deadline the
+    /// timer was armed with before.
+    pub(crate) fn wake_at(&mut self, deadline: Instant, waker: &Waker) {
+        let shared = self.shared.get_or_insert_with(|| {
+            let shared = Arc::new(TimerShared::default());
+            let thread_shared = Arc::clone(&shared);
+            thread::spawn(move || thread_shared.run());
+            shared
+        });
+        let mut state = shared.state.lock().unwrap();
+        match state.waker {
+            Some(ref current) if current.will_wake(waker) => {}
+            _ => state.waker = Some(waker.clone()),
+        }
+        if state.deadline != Some(deadline) {
+            state.deadline = Some(deadline);
+            shared.changed.notify_one();
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(ref shared) = self.shared {
+            shared.state.lock().unwrap().dropped = true;
+            shared.changed.notify_one();
+        }
+    }
+}
+
+impl TimerShared {
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.dropped {
+            let now = Instant::now();
+            state = match state.deadline {
+                None => self.changed.wait(state).unwrap(),
+                Some(deadline) if deadline > now => {
+                    self.changed.wait_timeout(state, deadline - now).unwrap().0
+                }
+                Some(_) => {
+                    state.deadline = None;
+                    let waker = state.waker.take();
+                    // the task may arm the timer again while it is woken
+                    drop(state);
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                    self.state.lock().unwrap()
+                }
+            };
+        }
+    }
+}
+
+// events are never pinned, only moved in and out of the queues
+impl<S> Unpin for Debounce<S>
+where
+    S: Stream + Unpin,
+    S::Item: Coalesce,
+{
+}
+
+impl<S> Stream for Debounce<S>
+where
+    S: Stream + Unpin,
+    S::Item: Coalesce,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let mut ended = false;
+        loop {
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(event)) => this.push(event),
+                Poll::Ready(None) => {
+                    ended = true;
+                    break;
+                }
+                Poll::Pending => break,
+            }
+        }
+        this.release(ended);
+        if let Some(event) = this.ready.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if ended {
+            return Poll::Ready(None);
+        }
+        this.arm_timer(cx);
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on_stream;
+    use futures::stream;
+
+    /// A value changing from `old` to `new`.
+    #[derive(Debug, PartialEq)]
+    struct Change {
+        key: Option<char>,
+        old: i32,
+        new: i32,
+    }
+
+    impl Coalesce for Change {
+        type Key = char;
+
+        fn coalesce_key(&self) -> Option<Self::Key> {
+            self.key
+        }
+
+        fn coalesce(self, later: Self) -> Option<Self> {
+            if self.old == later.new {
+                None
+            } else {
+                Some(Change {
+                    old: self.old,
+                    ..later
+                })
+            }
+        }
+    }
+
+    fn change(key: char, old: i32, new: i32) -> Change {
+        Change {
+            key: Some(key),
+            old,
+            new,
+        }
+    }
+
+    fn keyless(new: i32) -> Change {
+        Change {
+            key: None,
+            old: 0,
+            new,
+        }
+    }
+
+    fn debounce<T: Coalesce>(events: Vec<T>) -> Vec<T> {
+        block_on_stream(stream::iter(events).debounce(Duration::from_secs(60))).collect()
+    }
+
+    #[test]
+    fn coalesces_events_with_the_same_key() {
+        let events = vec![
+            change('a', 0, 1),
+            change('b', 0, 1),
+            change('a', 1, 2),
+            change('a', 2, 3),
+        ];
+        assert_eq!(debounce(events), vec![change('a', 0, 3), change('b', 0, 1)]);
+    }
+
+    #[test]
+    fn drops_changes_that_change_back() {
+        let events = vec![change('a', 0, 1), change('b', 0, 1), change('a', 1, 0)];
+        assert_eq!(debounce(events), vec![change('b', 0, 1)]);
+    }
+
+    #[test]
+    fn keeps_changes_after_a_cancelled_change() {
+        let events = vec![change('a', 0, 1), change('a', 1, 0), change('a', 0, 2)];
+        assert_eq!(debounce(events), vec![change('a', 0, 2)]);
+    }
+
+    #[test]
+    fn keyless_events_flush_pending_events() {
+        let events = vec![
+            change('a', 0, 1),
+            keyless(5),
+            change('a', 1, 0),
+            change('b', 0, 1),
+        ];
+        assert_eq!(
+            debounce(events),
+            vec![
+                change('a', 0, 1),
+                keyless(5),
+                change('a', 1, 0),
+                change('b', 0, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_flush_pending_events() {
+        let events: Vec<Result<Change, &str>> = vec![
+            Ok(change('a', 0, 1)),
+            Ok(change('a', 1, 2)),
+            Err("failed"),
+            Ok(change('a', 2, 1)),
+        ];
+        assert_eq!(
+            debounce(events),
+            vec![Ok(change('a', 0, 2)), Err("failed"), Ok(change('a', 2, 1))]
+        );
+    }
+
+    #[test]
+    fn zero_window_produces_every_event() {
+        let events = vec![change('a', 0, 1), change('a', 1, 0)];
+        let debounced = stream::iter(events).debounce(Duration::ZERO);
+        assert_eq!(
+            block_on_stream(debounced).collect::<Vec<_>>(),
+            vec![change('a', 0, 1), change('a', 1, 0)]
+        );
+    }
+
+    #[test]
+    fn holds_events_back_for_the_window() {
+        let window = Duration::from_millis(50);
+        let start = Instant::now();
+        // the source never ends, so only the timer releases the events
+        let events = stream::iter(vec![change('a', 0, 1), change('a', 1, 2)])
+            .chain(stream::pending())
+            .debounce(window);
+        assert_eq!(block_on_stream(events).next(), Some(change('a', 0, 2)));
+        assert!(start.elapsed() >= window);
+    }
+}
@@ -0,0 +1,322 @@
+//! Changes the Windows default audio devices.
+//!
+//! [`make_default`](fn.make_default.html) decides which defaults to change
+//! through the [`DefaultDevices`](trait.DefaultDevices.html) trait, so it can
+//! be used with something other than the devices of the current Windows
+//! session.
+
+use std::error::Error;
+
+use tracing::{debug, info};
+
+use crate::media::{DataFlow, DeviceEnumerator, DeviceRole, Endpoint, PolicyConfig};
+
+/// Reads and changes which devices are the defaults.
+pub trait DefaultDevices {
+    /// Gets the id of the default device of a flow for a role, if there is one.
+    fn default_device(
+        &self,
+        flow: DataFlow,
+        role: DeviceRole,
+    ) -> Result<Option<String>, Box<dyn Error>>;
+    /// Makes a device the default device for a role.
+    fn set_default_device(&self, id: &str, role: DeviceRole) -> Result<(), Box<dyn Error>>;
+}
+
+/// A device that is configured before it is made the default.
+pub(crate) trait ConfigurableDevice {
+    /// Gets the id of the device.
+    fn id(&self) -> Result<String, Box<dyn Error>>;
+    /// Gets whether the device plays or records sound.
+    fn flow(&self) -> Result<DataFlow, Box<dyn Error>>;
+    /// Checks whether the device is muted.
+    fn get_mute(&self) -> Result<bool, Box<dyn Error>>;
+    /// Mutes or unmutes the device.
+    fn set_mute(&self, mute: bool) -> Result<(), Box<dyn Error>>;
+}
+
+impl ConfigurableDevice for Endpoint {
+    fn id(&self) -> Result<String, Box<dyn Error>> {
+        Ok(Endpoint::id(self)?)
+    }
+
+    fn flow(&self) -> Result<DataFlow, Box<dyn Error>> {
+        Ok(Endpoint::flow(self)?)
+    }
+
+    fn get_mute(&self) -> Result<bool, Box<dyn Error>> {
+        Ok(Endpoint::get_mute(self)?)
+    }
+
+    fn set_mute(&self, mute: bool) -> Result<(), Box<dyn Error>> {
+        Ok(Endpoint::set_mute(self, mute)?)
+    }
+}
+
+/// The default devices of the current Windows session.
+#[derive(Debug)]
+pub struct SystemDefaults {
+    enumerator: DeviceEnumerator,
+    policy: PolicyConfig,
+}
+
+impl SystemDefaults {
+    /// Connects to the audio devices of the current Windows session.
+    pub fn new() -> windows::core::Result<Self> {
+        Ok(SystemDefaults {
+            enumerator: DeviceEnumerator::new()?,
+            policy: PolicyConfig::new()?,
+        })
+    }
+}
+
+impl DefaultDevices for SystemDefaults {
+    fn default_device(
+        &self,
+        flow: DataFlow,
+        role: DeviceRole,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        // there is no default device when no device of the flow is active
+        match self.enumerator.get_default_endpoint(flow, role) {
+            Ok(endpoint) => Ok(Some(endpoint.id()?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn set_default_device(&self, id: &str, role: DeviceRole) -> Result<(), Box<dyn Error>> {
+        Ok(self.policy.set_default_endpoint(id, role)?)
+    }
+}
+
+/// Makes a device the default device for some roles.
+///
+/// Roles the device is already the default for are left alone. Returns the
+/// roles that were changed, in the order they were given.
+pub fn make_default<D>(
+    devices: &D,
+    id: &str,
+    flow: DataFlow,
+    roles: &[DeviceRole],
+) -> Result<Vec<DeviceRole>, Box<dyn Error>>
+where
+    D: DefaultDevices + ?Sized,
+{
+    let mut changed = Vec::new();
+    for &role in roles {
+        if changed.contains(&role) {
+            continue;
+        }
+        if devices.default_device(flow, role)?.as_deref() == Some(id) {
+            debug!(id, %role, "Device is already the default");
+            continue;
+        }
+        info!(id, %role, "Making device the default");
+        devices.set_default_device(id, role)?;
+        changed.push(role);
+    }
+    Ok(changed)
+}
+
+/// Configures a device, then makes it the default device for some roles.
+///
+/// If `mute` is set, the device is muted while `configure` runs, and unmuted
+/// again afterwards. Once `configure` succeeds, the device is muted or
+/// unmuted as requested by `requested_mute`, if set.
+///
+/// Applications move their streams to a new default device, so the defaults
+/// are only changed once the device is fully configured, and not at all if
+/// configuring it fails.
+pub(crate) fn configure_and_make_default<D, C, F>(
+    devices: &D,
+    device: &C,
+    mute: bool,
+    requested_mute: Option<bool>,
+    roles: &[DeviceRole],
+    configure: F,
+) -> Result<Vec<DeviceRole>, Box<dyn Error>>
+where
+    D: DefaultDevices + ?Sized,
+    C: ConfigurableDevice + ?Sized,
+    F: FnOnce() -> Result<(), Box<dyn Error>>,
+{
+    let mute_unmute = mute && !device.get_mute()?;
+    if mute_unmute {
+        device.set_mute(true)?;
+    }
+    let result = configure();
+    // the requested mute state replaces the temporary one
+    let result = match (result, requested_mute) {
+        (Ok(()), Some(requested)) => device.set_mute(requested),
+        (result, _) => result,
+    };
+    if mute_unmute && !(result.is_ok() && requested_mute == Some(true)) {
+        device.set_mute(false)?;
+    }
+    result?;
+    make_default(devices, &device.id()?, device.flow()?, roles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::{Cell, RefCell};
+
+    const ID: &str = "{0.0.0.00000000}.{device}";
+
+    /// Records what is done to a single device and the defaults.
+    #[derive(Default)]
+    struct Fake {
+        defaults: RefCell<Vec<(DeviceRole, String)>>,
+        muted: Cell<bool>,
+        log: RefCell<Vec<String>>,
+    }
+
+    impl Fake {
+        fn with_default(role: DeviceRole, id: &str) -> Self {
+            let fake = Fake::default();
+            fake.defaults.borrow_mut().push((role, id.to_owned()));
+            fake
+        }
+
+        fn record(&self, entry: String) {
+            self.log.borrow_mut().push(entry);
+        }
+
+        fn log(&self) -> Vec<String> {
+            self.log.borrow().clone()
+        }
+
+        fn configure(&self, result: Result<(), &str>) -> Result<(), Box<dyn Error>> {
+            self.record("configure".to_owned());
+            result.map_err(Into::into)
+        }
+    }
+
+    impl DefaultDevices for Fake {
+        fn default_device(
+            &self,
+            flow: DataFlow,
+            role: DeviceRole,
+        ) -> Result<Option<String>, Box<dyn Error>> {
+            assert_eq!(flow, DataFlow::Render);
+            Ok(self
+                .defaults
+                .borrow()
+                .iter()
+                .find(|&&(r, _)| r == role)
+                .map(|(_, id)| id.clone()))
+        }
+
+        fn set_default_device(&self, id: &str, role: DeviceRole) -> Result<(), Box<dyn Error>> {
+            self.record(format!("default {}", role));
+            let mut defaults = self.defaults.borrow_mut();
+            defaults.retain(|&(r, _)| r != role);
+            defaults.push((role, id.to_owned()));
+            Ok(())
+        }
+    }
+
+    impl ConfigurableDevice for Fake {
+        fn id(&self) -> Result<String, Box<dyn Error>> {
+            Ok(ID.to_owned())
+        }
+
+        fn flow(&self) -> Result<DataFlow, Box<dyn Error>> {
+            Ok(DataFlow::Render)
+        }
+
+        fn get_mute(&self) -> Result<bool, Box<dyn Error>> {
+            Ok(self.muted.get())
+        }
+
+        fn set_mute(&self, mute: bool) -> Result<(), Box<dyn Error>> {
+            self.record(format!("mute {}", mute));
+            self.muted.set(mute);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn make_default_skips_current_and_repeated_roles() {
+        let fake = Fake::with_default(DeviceRole::Console, ID);
+        let changed = make_default(
+            &fake,
+            ID,
+            DataFlow::Render,
+            &[
+                DeviceRole::Console,
+                DeviceRole::Communications,
+                DeviceRole::Communications,
+                DeviceRole::Multimedia,
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            changed,
+            vec![DeviceRole::Communications, DeviceRole::Multimedia]
+        );
+        assert_eq!(
+            fake.log(),
+            vec!["default communications", "default multimedia"]
+        );
+    }
+
+    #[test]
+    fn makes_default_after_configuring_and_unmuting() {
+        let fake = Fake::with_default(DeviceRole::Console, "other");
+        let changed =
+            configure_and_make_default(&fake, &fake, true, None, &[DeviceRole::Console], || {
+                fake.configure(Ok(()))
+            })
+            .unwrap();
+        assert_eq!(changed, vec![DeviceRole::Console]);
+        assert_eq!(
+            fake.log(),
+            vec!["mute true", "configure", "mute false", "default console"]
+        );
+        assert!(!fake.muted.get());
+    }
+
+    #[test]
+    fn keeps_defaults_when_configuring_fails() {
+        let fake = Fake::with_default(DeviceRole::Console, "other");
+        let result = configure_and_make_default(
+            &fake,
+            &fake,
+            true,
+            Some(true),
+            &[DeviceRole::Console],
+            || fake.configure(Err("failed")),
+        );
+        assert_eq!(result.unwrap_err().to_string(), "failed");
+        assert_eq!(fake.log(), vec!["mute true", "configure", "mute false"]);
+        assert_eq!(
+            fake.default_device(DataFlow::Render, DeviceRole::Console)
+                .unwrap()
+                .as_deref(),
+            Some("other")
+        );
+    }
+
+    #[test]
+    fn requested_mute_replaces_temporary_mute() {
+        let fake = Fake::default();
+        configure_and_make_default(&fake, &fake, true, Some(true), &[], || {
+            fake.configure(Ok(()))
+        })
+        .unwrap();
+        assert_eq!(fake.log(), vec!["mute true", "configure", "mute true"]);
+        assert!(fake.muted.get());
+    }
+
+    #[test]
+    fn leaves_muted_device_muted() {
+        let fake = Fake::default();
+        fake.muted.set(true);
+        configure_and_make_default(&fake, &fake, true, None, &[], || fake.configure(Ok(())))
+            .unwrap();
+        assert_eq!(fake.log(), vec!["configure"]);
+        assert!(fake.muted.get());
+    }
+}
@@ -0,0 +1,768 @@
+//! Converts equalizer presets to and from the parameters of a device's
+//! equalizer.
+//!
+//! [`EqPreset`](struct.EqPreset.html) reads and writes the text format used
+//! by Equalizer APO and AutoEQ, with `Preamp:`, `Filter:`, and `GraphicEQ:`
+//! lines. [`Equalizer`](struct.Equalizer.html) describes the band parameters
+//! of a device's equalizer feature and maps a preset onto them.
+//!
+//! The equalizers of Sound Blaster devices are graphic equalizers with fixed
+//! bands, so parametric filters are approximated by evaluating their
+//! response at the center frequency of each band.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use regex::Regex;
+use tracing::{debug, warn};
+
+use crate::key::ItemKey;
+use crate::soundcore::{SoundCoreFeature, SoundCoreParamValue};
+use crate::{numeric_value, snap_to_range, Setting};
+
+/// The sample rate used to evaluate the response of filters.
+const SAMPLE_RATE: f64 = 48000.0;
+
+/// The center frequencies of a standard 10-band graphic equalizer.
+const OCTAVE_BANDS: [f64; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+
+/// The Q of a filter one octave wide.
+const OCTAVE_Q: f64 = std::f64::consts::SQRT_2;
+
+/// An equalizer preset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EqPreset {
+    /// The gain applied before the filters, in dB
+    pub preamp: f64,
+    /// Parametric filters
+    pub filters: Vec<EqFilter>,
+    /// Points of a graphic equalizer curve, as frequency in Hz and gain in dB
+    pub graphic: Vec<(f64, f64)>,
+}
+
+/// A parametric filter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EqFilter {
+    /// The shape of the filter
+    pub kind: FilterKind,
+    /// The center or corner frequency, in Hz
+    pub frequency: f64,
+    /// The gain, in dB
+    pub gain: f64,
+    /// The quality factor
+    pub q: f64,
+}
+
+/// The shape of a parametric filter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterKind {
+    /// A peaking filter (`PK`)
+    Peaking,
+    /// A low shelf filter (`LS` or `LSC`)
+    LowShelf,
+    /// A high shelf filter (`HS` or `HSC`)
+    HighShelf,
+}
+
+impl EqPreset {
+    /// Gets the gain of the filters and graphic curve at a frequency,
+    /// excluding the preamp.
+    pub fn gain_at(&self, frequency: f64) -> f64 {
+        let filters: f64 = self
+            .filters
+            .iter()
+            .map(|filter| filter.gain_at(frequency))
+            .sum();
+        filters + interpolate(&self.graphic, frequency)
+    }
+}
+
+impl EqFilter {
+    /// Gets the gain of the filter at a frequency, in dB.
+    pub fn gain_at(&self, frequency: f64) -> f64 {
+        // coefficients from the Audio EQ Cookbook by Robert Bristow-Johnson
+        let a = 10f64.powf(self.gain / 40.0);
+        let w0 = 2.0 * PI * self.frequency / SAMPLE_RATE;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * self.q);
+        let shelf = 2.0 * a.sqrt() * alpha;
+        let (b, a) = match self.kind {
+            FilterKind::Peaking => (
+                [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+                [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a],
+            ),
+            FilterKind::LowShelf => (
+                [
+                    a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                ],
+                [
+                    (a + 1.0) + (a - 1.0) * cos + shelf,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - shelf,
+                ],
+            ),
+            FilterKind::HighShelf => (
+                [
+                    a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                ],
+                [
+                    (a + 1.0) - (a - 1.0) * cos + shelf,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - shelf,
+                ],
+            ),
+        };
+        let w = 2.0 * PI * frequency / SAMPLE_RATE;
+        10.0 * (power(&b, w) / power(&a, w)).log10()
+    }
+}
+
+/// Gets the squared magnitude of a second order polynomial of `e^-jw`.
+fn power(coefficients: &[f64; 3], w: f64) -> f64 {
+    let real = coefficients[0] + coefficients[1] * w.cos() + coefficients[2] * (2.0 * w).cos();
+    let imaginary = coefficients[1] * w.sin() + coefficients[2] * (2.0 * w).sin();
+    real * real + imaginary * imaginary
+}
+
+/// Interpolates a graphic equalizer curve on a logarithmic frequency scale.
+fn interpolate(points: &[(f64, f64)], frequency: f64) -> f64 {
+    match (points.first(), points.last()) {
+        (Some(&(first, gain)), _) if frequency <= first => gain,
+        (_, Some(&(last, gain))) if last <= frequency => gain,
+        (Some(_), Some(_)) => {
+            let index = points.iter().position(|(f, _)| frequency < *f).unwrap();
+            let (f0, g0) = points[index - 1];
+            let (f1, g1) = points[index];
+            let t = (frequency / f0).ln() / (f1 / f0).ln();
+            g0 + (g1 - g0) * t
+        }
+        _ => 0.0,
+    }
+}
+
+impl FromStr for EqPreset {
+    type Err = ParseEqError;
+
+    /// Parses a preset in the Equalizer APO format, such as the
+    /// `ParametricEQ.txt` and `GraphicEQ.txt` files produced by AutoEQ.
+    ///
+    /// Lines that do not describe the preamp, a filter, or a graphic
+    /// equalizer curve are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut preset = EqPreset::default();
+        let mut found = false;
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            let invalid = || ParseEqError::InvalidLine {
+                line: index + 1,
+                input: line.to_owned(),
+            };
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (directive, rest) = match line.split_once(':') {
+                Some((directive, rest)) => (directive.trim(), rest),
+                None => {
+                    debug!(line = index + 1, "Ignoring line");
+                    continue;
+                }
+            };
+            if directive.eq_ignore_ascii_case("Preamp") {
+                let gain = rest.trim();
+                let gain = gain.strip_suffix("dB").unwrap_or(gain);
+                preset.preamp += finite(gain.trim()).ok_or_else(invalid)?;
+            } else if directive.eq_ignore_ascii_case("GraphicEQ") {
+                for point in rest.split(';').filter(|p| !p.trim().is_empty()) {
+                    let mut numbers = point.split_whitespace();
+                    match (numbers.next(), numbers.next(), numbers.next()) {
+                        (Some(frequency), Some(gain), None) => preset.graphic.push((
+                            positive(frequency).ok_or_else(invalid)?,
+                            finite(gain).ok_or_else(invalid)?,
+                        )),
+                        _ => return Err(invalid()),
+                    }
+                }
+                // the values are finite, so they can be compared
+                preset
+                    .graphic
+                    .sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            } else if directive
+                .split_whitespace()
+                .next()
+                .map_or(false, |word| word.eq_ignore_ascii_case("Filter"))
+            {
+                if let Some(filter) = parse_filter(rest).ok_or_else(invalid)? {
+                    preset.filters.push(filter);
+                }
+            } else {
+                debug!(line = index + 1, "Ignoring line");
+                continue;
+            }
+            found = true;
+        }
+        if found {
+            Ok(preset)
+        } else {
+            Err(ParseEqError::Empty)
+        }
+    }
+}
+
+/// Parses the part of a filter line after `Filter n:`.
+///
+/// Returns `Some(None)` for filters that are turned off.
+fn parse_filter(text: &str) -> Option<Option<EqFilter>> {
+    let mut tokens = text.split_whitespace();
+    let enabled = match tokens.next()? {
+        "ON" => true,
+        "OFF" => false,
+        _ => return None,
+    };
+    let kind = match tokens.next()? {
+        "PK" | "PEQ" | "Modal" => FilterKind::Peaking,
+        "LS" | "LSC" | "LSQ" => FilterKind::LowShelf,
+        "HS" | "HSC" | "HSQ" => FilterKind::HighShelf,
+        _ => return None,
+    };
+    let (mut frequency, mut gain, mut q) = (None, None, None);
+    while let Some(token) = tokens.next() {
+        match token {
+            "Fc" => frequency = Some(positive(tokens.next()?)?),
+            "Gain" => gain = Some(finite(tokens.next()?)?),
+            "Q" => q = Some(positive(tokens.next()?)?),
+            "BW" if tokens.next() == Some("Oct") => {
+                let bandwidth = 2f64.powf(positive(tokens.next()?)?);
+                q = Some(bandwidth.sqrt() / (bandwidth - 1.0));
+            }
+            // units and slopes such as "12dB"
+            _ => {}
+        }
+    }
+    let q = match (kind, q) {
+        (_, Some(q)) => q,
+        (FilterKind::Peaking, None) => return None,
+        (_, None) => std::f64::consts::FRAC_1_SQRT_2,
+    };
+    let filter = EqFilter {
+        kind,
+        frequency: frequency?,
+        gain: gain?,
+        q,
+    };
+    Some(if enabled { Some(filter) } else { None })
+}
+
+/// Parses a finite number.
+fn finite(text: &str) -> Option<f64> {
+    f64::from_str(text).ok().filter(|value| value.is_finite())
+}
+
+/// Parses a finite number greater than zero, such as a frequency or a Q.
+fn positive(text: &str) -> Option<f64> {
+    finite(text).filter(|value| *value > 0.0)
+}
+
+impl fmt::Display for EqPreset {
+    /// Writes the preset in the Equalizer APO format.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Preamp: {} dB", number(self.preamp))?;
+        for (index, filter) in self.filters.iter().enumerate() {
+            let kind = match filter.kind {
+                FilterKind::Peaking => "PK",
+                FilterKind::LowShelf => "LSC",
+                FilterKind::HighShelf => "HSC",
+            };
+            writeln!(
+                f,
+                "Filter {}: ON {} Fc {} Hz Gain {} dB Q {}",
+                index + 1,
+                kind,
+                number(filter.frequency),
+                number(filter.gain),
+                number(filter.q)
+            )?;
+        }
+        if !self.graphic.is_empty() {
+            let points: Vec<_> = self
+                .graphic
+                .iter()
+                .map(|(frequency, gain)| format!("{} {}", number(*frequency), number(*gain)))
+                .collect();
+            writeln!(f, "GraphicEQ: {}", points.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a number with at most two decimal places.
+fn number(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_owned(),
+        text => text.to_owned(),
+    }
+}
+
+/// Describes the equalizer feature of a device.
+#[derive(Clone, Debug)]
+pub struct Equalizer {
+    /// The description of the equalizer feature
+    pub feature: String,
+    /// The parameter that turns the equalizer on or off, if there is one
+    pub switch: Option<String>,
+    /// The preamp parameter, if there is one
+    pub preamp: Option<EqBand>,
+    /// The band parameters, ordered by frequency
+    pub bands: Vec<EqBand>,
+}
+
+/// Describes a parameter of an equalizer.
+#[derive(Clone, Debug)]
+pub struct EqBand {
+    /// The description of the parameter
+    pub parameter: String,
+    /// The center frequency of the band, in Hz, or zero for the preamp
+    pub frequency: f64,
+    /// The current gain, in dB
+    pub value: f64,
+    /// The minimum gain, if there is one
+    pub min: Option<f64>,
+    /// The maximum gain, if there is one
+    pub max: Option<f64>,
+    /// The distance between acceptable gains, if there is one
+    pub step: Option<f64>,
+}
+
+impl EqBand {
+    /// Converts a gain to an acceptable value for this parameter.
+    fn fit(&self, gain: f64) -> SoundCoreParamValue {
+        let value = snap_to_range(gain, self.min, self.max, self.step);
+        if (value - gain).abs() > 0.05 {
+            warn!(
+                parameter = %self.parameter,
+                requested = gain,
+                value,
+                "Gain is out of range",
+            );
+        }
+        SoundCoreParamValue::Float(value as f32)
+    }
+}
+
+impl Equalizer {
+    /// Finds the equalizer among the features of a device.
+    ///
+    /// If `feature` is `None`, the first feature with `EQ` or `Equalizer` in
+    /// its description is used.
+    pub(crate) fn find(
+        features: &[SoundCoreFeature],
+        feature: Option<&str>,
+    ) -> Result<Equalizer, Box<dyn Error>> {
+        let index = match feature {
+            Some(key) => {
+                ItemKey::parse(key).find("feature", features, |f| f.id, |f| &f.description)?
+            }
+            None => {
+                let pattern = Regex::new(r"(?i)\bEQ|equali[sz]er").unwrap();
+                features
+                    .iter()
+                    .position(|f| pattern.is_match(&f.description))
+            }
+        };
+        let feature =
+            &features[index.ok_or_else(|| EqualizerError::NotFound(feature.map(str::to_owned)))?];
+
+        let frequency_pattern = Regex::new(r"(?i)(\d+(?:\.\d+)?)\s*(?:(k)(?:hz)?|hz)").unwrap();
+        let mut switches = Vec::new();
+        let mut preamp = None;
+        let mut bands = Vec::new();
+        let mut unnamed = Vec::new();
+        for parameter in feature.parameters() {
+            let parameter = parameter?;
+            if parameter.attributes & 1 != 0 {
+                continue;
+            }
+            match parameter.kind {
+                1 => switches.push(parameter.description),
+                0 => {
+                    let mut band = EqBand {
+                        frequency: 0.0,
+                        value: numeric_value(&parameter.get()?).unwrap_or(0.0),
+                        min: numeric_value(&parameter.min_value),
+                        max: numeric_value(&parameter.max_value),
+                        step: numeric_value(&parameter.step_size).filter(|step| *step > 0.0),
+                        parameter: parameter.description,
+                    };
+                    if band.parameter.to_lowercase().contains("pre") {
+                        preamp = Some(band);
+                    } else if let Some(captures) = frequency_pattern.captures(&band.parameter) {
+                        let scale = if captures.get(2).is_some() {
+                            1000.0
+                        } else {
+                            1.0
+                        };
+                        band.frequency = f64::from_str(&captures[1]).unwrap() * scale;
+                        if band.frequency.is_finite() && band.frequency > 0.0 {
+                            bands.push(band);
+                        } else {
+                            debug!(parameter = %band.parameter, "Ignoring band with an invalid frequency");
+                        }
+                    } else {
+                        unnamed.push(band);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if bands.is_empty() && unnamed.len() == OCTAVE_BANDS.len() {
+            debug!("Assuming standard octave bands");
+            for (mut band, frequency) in unnamed.into_iter().zip(OCTAVE_BANDS) {
+                band.frequency = frequency;
+                bands.push(band);
+            }
+        } else {
+            for band in unnamed {
+                debug!(parameter = %band.parameter, "Ignoring parameter without a frequency");
+            }
+        }
+        if bands.is_empty() {
+            return Err(Box::new(EqualizerError::NoBands(
+                feature.description.clone(),
+            )));
+        }
+        bands.sort_by(|a, b| {
+            a.frequency
+                .partial_cmp(&b.frequency)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        Ok(Equalizer {
+            feature: feature.description.clone(),
+            // only use the switch if it is unambiguous
+            switch: match switches.len() {
+                1 => switches.pop(),
+                _ => None,
+            },
+            preamp,
+            bands,
+        })
+    }
+
+    /// Maps a preset onto the parameters of this equalizer.
+    ///
+    /// Gains are clamped to the range of each parameter and rounded to its
+    /// step size. If the equalizer has no preamp parameter, the preamp is
+    /// added to every band. The equalizer is turned on if it has a switch.
+    pub fn settings(&self, preset: &EqPreset) -> IndexMap<String, Setting<SoundCoreParamValue>> {
+        let mut settings = IndexMap::new();
+        if let Some(switch) = &self.switch {
+            settings.insert(
+                switch.clone(),
+                Setting::Absolute(SoundCoreParamValue::Bool(true)),
+            );
+        }
+        let offset = match &self.preamp {
+            Some(preamp) => {
+                settings.insert(
+                    preamp.parameter.clone(),
+                    Setting::Absolute(preamp.fit(preset.preamp)),
+                );
+                0.0
+            }
+            None => preset.preamp,
+        };
+        for band in &self.bands {
+            let gain = preset.gain_at(band.frequency) + offset;
+            settings.insert(band.parameter.clone(), Setting::Absolute(band.fit(gain)));
+        }
+        settings
+    }
+
+    /// Captures the current gains of this equalizer as a preset.
+    ///
+    /// If `parametric` is true, each band is written as a peaking filter an
+    /// octave wide. Otherwise, the bands are written as a graphic equalizer
+    /// curve.
+    pub fn preset(&self, parametric: bool) -> EqPreset {
+        let preamp = self.preamp.as_ref().map_or(0.0, |preamp| preamp.value);
+        if parametric {
+            EqPreset {
+                preamp,
+                filters: self
+                    .bands
+                    .iter()
+                    .map(|band| EqFilter {
+                        kind: FilterKind::Peaking,
+                        frequency: band.frequency,
+                        gain: band.value,
+                        q: OCTAVE_Q,
+                    })
+                    .collect(),
+                graphic: Vec::new(),
+            }
+        } else {
+            EqPreset {
+                preamp,
+                filters: Vec::new(),
+                graphic: self
+                    .bands
+                    .iter()
+                    .map(|band| (band.frequency, band.value))
+                    .collect(),
+            }
+        }
+    }
+}
+
+/// Describes a failure to parse an equalizer preset.
+#[derive(Debug)]
+pub enum ParseEqError {
+    /// A line could not be parsed.
+    InvalidLine {
+        /// The line number, starting from 1
+        line: usize,
+        /// The text of the line
+        input: String,
+    },
+    /// The text does not contain any equalizer settings.
+    Empty,
+}
+
+impl fmt::Display for ParseEqError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseEqError::InvalidLine { line, input } => {
+                write!(f, "invalid equalizer setting on line {}: {:?}", line, input)
+            }
+            ParseEqError::Empty => f.write_str("no Preamp, Filter, or GraphicEQ lines found"),
+        }
+    }
+}
+
+impl Error for ParseEqError {}
+
+/// Describes a failure to find a device's equalizer.
+#[derive(Debug)]
+pub enum EqualizerError {
+    /// No feature matches the given key, or no feature looks like an
+    /// equalizer.
+    NotFound(Option<String>),
+    /// The feature has no parameters that look like equalizer bands.
+    NoBands(String),
+}
+
+impl fmt::Display for EqualizerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EqualizerError::NotFound(Some(feature)) => write!(f, "unknown feature {:?}", feature),
+            EqualizerError::NotFound(None) => {
+                f.write_str("no equalizer found; use --feature to select one")
+            }
+            EqualizerError::NoBands(feature) => {
+                write!(f, "feature {:?} has no equalizer bands", feature)
+            }
+        }
+    }
+}
+
+impl Error for EqualizerError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band(parameter: &str, frequency: f64) -> EqBand {
+        EqBand {
+            parameter: parameter.to_owned(),
+            frequency,
+            value: 0.0,
+            min: Some(-12.0),
+            max: Some(12.0),
+            step: Some(0.5),
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn parses_parametric_preset() {
+        let preset = EqPreset::from_str(
+            "Preamp: -6.4 dB\n\
+             Filter 1: ON LSC Fc 105 Hz Gain 5.5 dB Q 0.70\n\
+             Filter 2: ON PK Fc 1979 Hz Gain 3.3 dB Q 1.43\n\
+             Filter 3: OFF PK Fc 5000 Hz Gain 2 dB Q 1\n",
+        )
+        .unwrap();
+        assert_eq!(preset.preamp, -6.4);
+        assert_eq!(
+            preset.filters,
+            vec![
+                EqFilter {
+                    kind: FilterKind::LowShelf,
+                    frequency: 105.0,
+                    gain: 5.5,
+                    q: 0.7,
+                },
+                EqFilter {
+                    kind: FilterKind::Peaking,
+                    frequency: 1979.0,
+                    gain: 3.3,
+                    q: 1.43,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_directives_case_insensitively() {
+        let preset = EqPreset::from_str("preamp: -1\nfilter: ON PK Fc 100 Gain 1 Q 1").unwrap();
+        assert_eq!(preset.preamp, -1.0);
+        assert_eq!(preset.filters.len(), 1);
+    }
+
+    #[test]
+    fn converts_bandwidth_to_q() {
+        let preset = EqPreset::from_str("Filter: ON PK Fc 1000 Gain 3 BW Oct 1").unwrap();
+        assert_close(preset.filters[0].q, OCTAVE_Q);
+    }
+
+    #[test]
+    fn sorts_graphic_curve() {
+        let preset = EqPreset::from_str("GraphicEQ: 1000 2; 20 -1; 100 0").unwrap();
+        assert_eq!(
+            preset.graphic,
+            vec![(20.0, -1.0), (100.0, 0.0), (1000.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        for text in [
+            "Preamp: nan dB",
+            "GraphicEQ: nan 0",
+            "GraphicEQ: 20 inf",
+            "GraphicEQ: 0 1",
+            "Filter 1: ON PK Fc 1000 Hz Gain 1 dB Q 0",
+            "Filter 1: ON PK Fc -5 Hz Gain 1 dB Q 1",
+            "Filter 1: ON PK Fc 1000 Hz Gain 1 dB BW Oct 0",
+            "Filter 1: ON PK Fc 1000 Hz Gain NaN dB Q 1",
+        ] {
+            match EqPreset::from_str(text) {
+                Err(ParseEqError::InvalidLine { line: 1, .. }) => {}
+                other => panic!("{:?} parsed as {:?}", text, other),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_empty_preset() {
+        assert!(matches!(
+            EqPreset::from_str("# nothing here\n"),
+            Err(ParseEqError::Empty)
+        ));
+    }
+
+    #[test]
+    fn writes_parseable_preset() {
+        let preset = EqPreset {
+            preamp: -3.0,
+            filters: vec![EqFilter {
+                kind: FilterKind::HighShelf,
+                frequency: 8000.0,
+                gain: -2.5,
+                q: 0.71,
+            }],
+            graphic: vec![(20.0, 1.0), (20000.0, -1.0)],
+        };
+        assert_eq!(EqPreset::from_str(&preset.to_string()).unwrap(), preset);
+    }
+
+    #[test]
+    fn peaking_filter_has_its_gain_at_the_center() {
+        let filter = EqFilter {
+            kind: FilterKind::Peaking,
+            frequency: 1000.0,
+            gain: 6.0,
+            q: 1.0,
+        };
+        assert_close(filter.gain_at(1000.0), 6.0);
+        assert!(filter.gain_at(50.0).abs() < 0.1);
+        assert!(filter.gain_at(20000.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn shelf_filters_reach_their_gain() {
+        let low = EqFilter {
+            kind: FilterKind::LowShelf,
+            frequency: 200.0,
+            gain: 4.0,
+            q: std::f64::consts::FRAC_1_SQRT_2,
+        };
+        assert_close(low.gain_at(10.0), 4.0);
+        assert_close(low.gain_at(200.0), 2.0);
+        assert!(low.gain_at(10000.0).abs() < 0.1);
+        let high = EqFilter {
+            kind: FilterKind::HighShelf,
+            ..low
+        };
+        assert!(high.gain_at(10.0).abs() < 0.1);
+        assert_close(high.gain_at(200.0), 2.0);
+    }
+
+    #[test]
+    fn interpolates_graphic_curve_logarithmically() {
+        let points = [(100.0, 0.0), (1000.0, 10.0)];
+        assert_close(interpolate(&points, 50.0), 0.0);
+        assert_close(interpolate(&points, 316.2278), 5.0);
+        assert_close(interpolate(&points, 2000.0), 10.0);
+        assert_close(interpolate(&[], 1000.0), 0.0);
+    }
+
+    #[test]
+    fn maps_preset_onto_bands() {
+        let equalizer = Equalizer {
+            feature: "EQ".to_owned(),
+            switch: Some("EQ Enable".to_owned()),
+            preamp: None,
+            bands: vec![band("100 Hz", 100.0), band("1 kHz", 1000.0)],
+        };
+        let preset = EqPreset {
+            preamp: -1.2,
+            filters: Vec::new(),
+            graphic: vec![(100.0, 20.0), (1000.0, 3.0)],
+        };
+        let settings = equalizer.settings(&preset);
+        assert_eq!(
+            settings["EQ Enable"],
+            Setting::Absolute(SoundCoreParamValue::Bool(true))
+        );
+        // clamped to the range, including the preamp
+        assert_eq!(
+            settings["100 Hz"],
+            Setting::Absolute(SoundCoreParamValue::Float(12.0))
+        );
+        // rounded to the step size
+        assert_eq!(
+            settings["1 kHz"],
+            Setting::Absolute(SoundCoreParamValue::Float(2.0))
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Provides a device handle that can be shared between threads.
+
+use futures::executor::{self, BlockingStream};
+use windows::core::HSTRING;
+
+use std::error::Error;
+
+use crate::asynchronous::{ComThread, ComThreadError, EventStream, SoundCoreNotification};
+use crate::key::ItemKey;
+use crate::soundcore::{EventMask, SoundCore, SoundCoreParamValue};
+use crate::{get_endpoint, Configuration, DeviceInfo, DumpOptions, SetReport};
+
+/// Controls a device from any thread.
+///
+/// `SoundCore` and `Endpoint` can only be used on the thread that created
+/// them. A `DeviceHandle` instead owns a dedicated COM thread and forwards
+/// each request to it, so it is `Send`, `Sync`, and `Clone`. Clones share
+/// the thread, which stops when the last clone and event iterator are
+/// dropped.
+///
+/// Each method blocks the calling thread until the COM thread has handled
+/// the request. Use [`thread`](#method.thread) for the async equivalents.
+///
+/// # Examples
+///
+/// ```
+/// let device = DeviceHandle::open(None)?;
+/// let worker = {
+///     let device = device.clone();
+///     thread::spawn(move || device.get("Device Control", "SelectOutput"))
+/// };
+/// println!("{:?}", worker.join().unwrap()?);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeviceHandle {
+    thread: ComThread,
+    id: String,
+}
+
+// sharing between threads is the point of the handle
+const _: fn() = || {
+    fn assert_shareable<T: Send + Sync + Clone>() {}
+    assert_shareable::<DeviceHandle>();
+};
+
+impl DeviceHandle {
+    /// Opens a device, starting a COM thread for it.
+    ///
+    /// If `device_id` is `None`, the system default output device at the
+    /// time of the call is used, even if the default changes later.
+    pub fn open(device_id: Option<&str>) -> Result<DeviceHandle, ComThreadError> {
+        let thread = ComThread::new();
+        let device_id = device_id.map(HSTRING::from);
+        let id =
+            executor::block_on(thread.call(move || Ok(get_endpoint(device_id.as_ref())?.id()?)))?;
+        Ok(DeviceHandle { thread, id })
+    }
+
+    /// Gets the id of the device.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Gets the COM thread of the device, for use with the async API.
+    pub fn thread(&self) -> &ComThread {
+        &self.thread
+    }
+
+    /// Runs a function on the COM thread with the device id, and waits for
+    /// its result.
+    fn call<F, T>(&self, f: F) -> Result<T, ComThreadError>
+    where
+        F: FnOnce(&HSTRING) -> Result<T, Box<dyn Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let id = self.id.clone();
+        executor::block_on(self.thread.call(move || f(&HSTRING::from(id))))
+    }
+
+    /// Gets information about the device.
+    pub fn info(&self) -> Result<DeviceInfo, ComThreadError> {
+        self.call(|id| crate::device_info(Some(id)))
+    }
+
+    /// Reads a parameter in the default context.
+    ///
+    /// This is the context that [`set`](#method.set) changes through the
+    /// `creative` section of a configuration. `feature` and `parameter` are
+    /// descriptions or `#id` keys, as in a configuration.
+    pub fn get(
+        &self,
+        feature: &str,
+        parameter: &str,
+    ) -> Result<SoundCoreParamValue, ComThreadError> {
+        let feature_key = feature.to_owned();
+        let parameter_key = parameter.to_owned();
+        self.call(move |id| {
+            let endpoint = get_endpoint(Some(id))?;
+            let core = SoundCore::for_device(&endpoint.clsid()?, &endpoint.id()?)?;
+            let features = core.features(0).collect::<Result<Vec<_>, _>>()?;
+            let feature = match ItemKey::parse(&feature_key).find(
+                "feature",
+                &features,
+                |f| f.id,
+                |f| &f.description,
+            )? {
+                Some(index) => &features[index],
+                None => return Err(format!("Could not find feature {}", feature_key).into()),
+            };
+            let parameters = feature.parameters().collect::<Result<Vec<_>, _>>()?;
+            match ItemKey::parse(&parameter_key).find(
+                "parameter",
+                &parameters,
+                |p| p.id,
+                |p| &p.description,
+            )? {
+                Some(index) => Ok(parameters[index].get()?),
+                None => Err(format!(
+                    "Could not find parameter {}.{}",
+                    feature.description, parameter_key
+                )
+                .into()),
+            }
+        })
+    }
+
+    /// Applies a configuration to the device.
+    ///
+    /// See [`set`](../fn.set.html).
+    pub fn set(
+        &self,
+        configuration: Configuration,
+        mute: bool,
+    ) -> Result<SetReport, ComThreadError> {
+        self.call(move |id| crate::set(Some(id), &configuration, mute))
+    }
+
+    /// Captures a snapshot of the device's configuration.
+    ///
+    /// See [`dump_with_options`](../fn.dump_with_options.html).
+    pub fn dump(&self, options: DumpOptions) -> Result<Configuration, ComThreadError> {
+        self.call(move |id| crate::dump_with_options(Some(id), &options))
+    }
+
+    /// Iterates over the SoundCore events of the device.
+    ///
+    /// The iterator can be moved to another thread, and blocks until the
+    /// next event is available.
+    pub fn watch(
+        &self,
+        mask: EventMask,
+    ) -> BlockingStream<EventStream<Result<SoundCoreNotification, ComThreadError>>> {
+        executor::block_on_stream(self.thread.sound_core_events(Some(self.id.clone()), mask))
+    }
+}
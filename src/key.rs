@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use tracing::warn;
+
+/// Identifies a feature or parameter in a configuration.
+///
+/// Keys are usually descriptions, but may also be written as `#id` or
+/// `#id description` to address an item whose description is truncated or
+/// shared with another item.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemKey<'a> {
+    /// An `#id` key, with the description that follows the id, if any
+    Id(u32, Option<&'a str>),
+    /// A description
+    Description(&'a str),
+}
+
+impl<'a> ItemKey<'a> {
+    /// Parses a feature or parameter key.
+    pub fn parse(key: &'a str) -> Self {
+        if let Some(rest) = key.strip_prefix('#') {
+            let (number, description) = match rest.split_once(char::is_whitespace) {
+                Some((number, description)) => (number, Some(description.trim())),
+                None => (rest, None),
+            };
+            if let Ok(id) = u32::from_str(number) {
+                return ItemKey::Id(id, description.filter(|d| !d.is_empty()));
+            }
+        }
+        ItemKey::Description(key)
+    }
+
+    /// Gets the description the key refers to an item by, if it has one.
+    pub fn description(self) -> Option<&'a str> {
+        match self {
+            ItemKey::Id(_, description) => description,
+            ItemKey::Description(description) => Some(description),
+        }
+    }
+
+    /// Formats the key used to write an item in a configuration.
+    pub(crate) fn format(id: u32, description: &str, include_id: bool) -> String {
+        if include_id {
+            format!("#{} {}", id, description)
+        } else {
+            description.to_owned()
+        }
+    }
+
+    /// Finds the position of the item this key refers to.
+    pub(crate) fn find<T>(
+        self,
+        kind: &'static str,
+        items: &[T],
+        id: impl Fn(&T) -> u32,
+        description: impl Fn(&T) -> &str,
+    ) -> Result<Option<usize>, AmbiguousKeyError> {
+        match self {
+            ItemKey::Id(wanted, expected) => {
+                let index = items.iter().position(|item| id(item) == wanted);
+                if let (Some(index), Some(expected)) = (index, expected) {
+                    let actual = description(&items[index]);
+                    if actual != expected {
+                        warn!(
+                            id = wanted,
+                            expected, actual, "Description of {} does not match", kind
+                        );
+                    }
+                }
+                Ok(index)
+            }
+            ItemKey::Description(wanted) => {
+                let mut matches = items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, item)| description(item) == wanted);
+                let first = matches.next();
+                let rest: Vec<_> = matches.map(|(_, item)| id(item)).collect();
+                match first {
+                    Some((_, item)) if !rest.is_empty() => Err(AmbiguousKeyError {
+                        kind,
+                        description: wanted.to_owned(),
+                        ids: std::iter::once(id(item)).chain(rest).collect(),
+                    }),
+                    Some((index, _)) => Ok(Some(index)),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+}
+
+/// Finds descriptions shared by more than one item.
+pub(crate) fn duplicate_descriptions<'a, I>(descriptions: I) -> BTreeMap<&'a str, usize>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut counts = BTreeMap::new();
+    for description in descriptions {
+        *counts.entry(description).or_insert(0) += 1;
+    }
+    counts.retain(|_, count| *count > 1);
+    counts
+}
+
+/// Describes a description that matches more than one feature or parameter.
+#[derive(Debug)]
+pub(crate) struct AmbiguousKeyError {
+    kind: &'static str,
+    description: String,
+    ids: Vec<u32>,
+}
+
+impl fmt::Display for AmbiguousKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ids: Vec<_> = self.ids.iter().map(|id| format!("#{}", id)).collect();
+        write!(
+            f,
+            "{} {:?} is ambiguous; it could be any of {}",
+            self.kind,
+            self.description,
+            ids.join(", ")
+        )
+    }
+}
+
+impl Error for AmbiguousKeyError {}
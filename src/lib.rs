@@ -6,11 +6,23 @@
 //!
 //! For an even-lower-level API, see [`mmdeviceapi`](../winapi/um/mmdeviceapi/index.html) and [`ctsndcr`](ctsndcr/index.html).
 
+pub mod alias;
+pub mod asynchronous;
+pub mod catalog;
 mod com;
 pub mod ctsndcr;
+mod debounce;
+pub mod defaults;
+pub mod eq;
+pub mod handle;
+mod key;
 mod lazy;
 pub mod media;
+mod policyconfig;
+pub mod selector;
+mod setting;
 pub mod soundcore;
+mod volume;
 mod winapiext;
 
 use futures::stream::Fuse;
@@ -18,21 +30,37 @@ use futures::task::Context;
 use futures::{Stream, StreamExt};
 
 use indexmap::IndexMap;
-use tracing::{debug, debug_span, error, trace_span, warn};
-use windows::core::PCWSTR;
+use tracing::{debug, debug_span, error, info, trace_span, warn};
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
 
-use std::collections::BTreeSet;
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt;
 use std::pin::Pin;
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
+use crate::catalog::{Catalog, Model, ParameterInfo};
+pub use crate::com::event::CancelHandle;
 use crate::com::event::ComEventIterator;
-use crate::media::{DeviceEnumerator, Endpoint, VolumeEvents, VolumeNotification};
+use crate::debounce::{Coalesce, Debounce, DebounceExt, Timer};
+use crate::defaults::{configure_and_make_default, make_default, SystemDefaults};
+use crate::eq::Equalizer;
+use crate::key::duplicate_descriptions;
+pub use crate::key::ItemKey;
+use crate::media::{
+    ClsidSource, DataFlow, DeviceEnumerator, DeviceEvents, DeviceNotification, DeviceRole,
+    DeviceState, Endpoint, PolicyConfig, VolumeEvents, VolumeNotification,
+};
+use crate::selector::{DeviceSelector, Pattern};
+pub use crate::setting::Setting;
 use crate::soundcore::{
-    SoundCore, SoundCoreEvent, SoundCoreEventIterator, SoundCoreEvents, SoundCoreFeature,
-    SoundCoreParamValue, SoundCoreParameter,
+    EventMask, SoundCore, SoundCoreContext, SoundCoreError, SoundCoreEvent, SoundCoreEventIterator,
+    SoundCoreEvents, SoundCoreFeature, SoundCoreParamValue, SoundCoreParameter,
 };
+pub use crate::volume::{ParseVolumeError, Volume, VolumeOutOfRangeError};
 
 #[cfg(not(any(target_arch = "x86", feature = "ctsndcr_ignore_arch")))]
 compile_error!("This crate must be built for x86 for compatibility with sound drivers." +
@@ -41,8 +69,12 @@ compile_error!("This crate must be built for x86 for compatibility with sound dr
 /// Describes the configuration of a media endpoint.
 #[derive(Debug)]
 pub struct EndpointConfiguration {
-    /// The desired volume level, from 0.0 to 1.0
-    pub volume: Option<f32>,
+    /// The desired volume level, or an adjustment to the current level
+    pub volume: Option<Setting<Volume>>,
+    /// Whether the endpoint should be muted
+    pub mute: Option<bool>,
+    /// The roles the endpoint should become the default device for
+    pub default: Vec<DeviceRole>,
 }
 
 /// Describes a configuration to be applied.
@@ -51,17 +83,121 @@ pub struct Configuration {
     /// Windows audio endpoint settings
     pub endpoint: Option<EndpointConfiguration>,
     /// Creative SoundBlaster settings
-    pub creative: Option<IndexMap<String, IndexMap<String, SoundCoreParamValue>>>,
+    ///
+    /// Features and parameters are keyed by description, or by numeric id
+    /// written as `#id` or `#id description`. A description that matches
+    /// more than one feature or parameter is an error.
+    ///
+    /// Relative settings are added to the current value of the parameter,
+    /// and the result is clamped to the range of the parameter.
+    pub creative: Option<IndexMap<String, IndexMap<String, Setting<SoundCoreParamValue>>>>,
+    /// Creative SoundBlaster settings for other contexts
+    ///
+    /// Contexts are keyed by description or `#id`, and their settings are
+    /// applied like those in `creative`, which always refers to context 0.
+    #[allow(clippy::type_complexity)]
+    pub contexts:
+        Option<IndexMap<String, IndexMap<String, IndexMap<String, Setting<SoundCoreParamValue>>>>>,
+    /// Other devices to enable (`true`) or disable (`false`)
+    ///
+    /// Devices are keyed by id or by [`DeviceSelector`](selector/enum.DeviceSelector.html),
+    /// which may also select disabled devices. They are enabled or disabled
+    /// before the rest of the configuration is applied.
+    pub endpoints: Option<IndexMap<String, bool>>,
+}
+
+/// Controls how a device's configuration is captured.
+#[derive(Clone, Debug, Default)]
+pub struct DumpOptions {
+    /// Capture the endpoint volume in decibels instead of as a scalar.
+    pub volume_in_decibels: bool,
+    /// Key every feature and parameter as `#id description`.
+    ///
+    /// Items whose descriptions are shared with another item are always
+    /// keyed this way.
+    pub include_ids: bool,
+    /// Capture this context, given as a description or `#id`, into
+    /// `contexts` instead of capturing context 0 into `creative`.
+    pub context: Option<String>,
+    /// Capture every context the device lists.
+    pub all_contexts: bool,
 }
 
+/// Settings for the parameters of each feature of a context.
+type CreativeSettings = IndexMap<String, IndexMap<String, Setting<SoundCoreParamValue>>>;
+
 /// Describes a device that may be configurable.
 pub struct DeviceInfo {
     /// Represents the device to Windows.
     pub id: String,
-    /// Describes the hardware that connects the device to the computer.
-    pub interface: String,
-    /// Describes the audio device.
-    pub description: String,
+    /// Describes the hardware that connects the device to the computer, or
+    /// `None` if it could not be read.
+    pub interface: Option<String>,
+    /// Describes the audio device, or `None` if it could not be read.
+    pub description: Option<String>,
+    /// The property the SoundCore CLSID was found in, or `None` if the device
+    /// cannot be controlled through SoundCore or the CLSID could not be read.
+    pub soundcore: Option<ClsidSource>,
+    /// The roles the device is the default device for.
+    pub default_for: Vec<DeviceRole>,
+    /// Whether the device can be used.
+    pub state: DeviceState,
+    /// Whether the device plays or records sound.
+    pub flow: DataFlow,
+}
+
+impl DeviceInfo {
+    /// Gathers information about a device.
+    ///
+    /// Properties that cannot be read are logged and left out, so that one
+    /// misbehaving device does not hide the others.
+    fn new(
+        endpoint: &Endpoint,
+        defaults: &[(DataFlow, DeviceRole, String)],
+    ) -> Result<Self, Box<dyn Error>> {
+        let id = endpoint.id()?;
+        let flow = endpoint.flow()?;
+        let soundcore = match endpoint.clsid_with_source() {
+            Ok((_, source)) => Some(source),
+            Err(SoundCoreError::NotSupported) => None,
+            Err(error) => {
+                warn!(id = id.as_str(), error = %error, "Could not read SoundCore CLSID");
+                None
+            }
+        };
+        let interface = endpoint.interface().map_err(|error| {
+            warn!(id = id.as_str(), error = %error, "Could not read device interface");
+        });
+        let description = endpoint.description().map_err(|error| {
+            warn!(id = id.as_str(), error = %error, "Could not read device description");
+        });
+        let state = endpoint.state().unwrap_or_else(|error| {
+            warn!(id = id.as_str(), error = %error, "Could not read device state");
+            DeviceState::Unknown(0)
+        });
+        Ok(DeviceInfo {
+            default_for: defaults
+                .iter()
+                .filter(|(f, _, default)| *f == flow && *default == id)
+                .map(|(_, role, _)| *role)
+                .collect(),
+            interface: interface.ok(),
+            description: description.ok(),
+            state,
+            soundcore,
+            flow,
+            id,
+        })
+    }
+}
+
+/// Controls which devices are listed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ListOptions {
+    /// Whether to list outputs, inputs, or both if `None`
+    pub flow: Option<DataFlow>,
+    /// Whether to include devices that are disabled, unplugged, or not present
+    pub include_inactive: bool,
 }
 
 /// Produces a list of devices currently available.
@@ -72,26 +208,222 @@ pub struct DeviceInfo {
 ///
 /// ```
 /// for device in list_devices()? {
-///     println!("{}: {}", device.id, device.description);
+///     println!("{}: {:?}", device.id, device.description);
 /// }
 /// ```
 pub fn list_devices() -> Result<Vec<DeviceInfo>, Box<dyn Error>> {
-    let endpoints = DeviceEnumerator::new()?.get_active_audio_endpoints()?;
+    list_devices_with_options(&ListOptions {
+        flow: Some(DataFlow::Render),
+        include_inactive: false,
+    })
+}
+
+/// Produces a list of devices.
+///
+/// This may include devices that are not configurable.
+///
+/// # Examples
+///
+/// ```
+/// let options = ListOptions {
+///     include_inactive: true,
+///     ..ListOptions::default()
+/// };
+/// for device in list_devices_with_options(&options)? {
+///     println!("{}: {:?} ({})", device.id, device.description, device.state.name());
+/// }
+/// ```
+pub fn list_devices_with_options(options: &ListOptions) -> Result<Vec<DeviceInfo>, Box<dyn Error>> {
+    let enumerator = DeviceEnumerator::new()?;
+    let defaults = default_devices(&enumerator)?;
+    let endpoints = enumerator.get_audio_endpoints(options.flow, options.include_inactive)?;
     let mut result = Vec::with_capacity(endpoints.len());
     for endpoint in endpoints {
-        let id = endpoint.id()?;
-        let span = debug_span!("Querying endpoint...", endpoint_id = id);
-        let _span = span.enter();
-        result.push(DeviceInfo {
-            id,
-            interface: endpoint.interface()?,
-            description: endpoint.description()?,
-        })
+        match DeviceInfo::new(&endpoint, &defaults) {
+            Ok(device) => result.push(device),
+            // the device cannot even be identified
+            Err(error) => warn!(error = %error, "Could not query device; skipping it"),
+        }
     }
     Ok(result)
 }
 
-fn get_endpoint<I>(device_id: Option<I>) -> windows::core::Result<Endpoint>
+/// Finds the default device for each flow and role.
+fn default_devices(
+    enumerator: &DeviceEnumerator,
+) -> windows::core::Result<Vec<(DataFlow, DeviceRole, String)>> {
+    let mut defaults = Vec::new();
+    for flow in [DataFlow::Render, DataFlow::Capture] {
+        for role in DeviceRole::ALL {
+            // there is no default if there are no devices
+            if let Ok(endpoint) = enumerator.get_default_endpoint(flow, role) {
+                defaults.push((flow, role, endpoint.id()?));
+            }
+        }
+    }
+    Ok(defaults)
+}
+
+/// Describes the features and parameters of a device.
+pub struct DeviceDescription {
+    /// The device
+    pub device: DeviceInfo,
+    /// The model of the device, if it is in the catalog
+    pub model: Option<Model>,
+    /// The SoundCore contexts of the device
+    pub contexts: Vec<SoundCoreContext>,
+    /// The features of the device
+    pub features: Vec<FeatureDescription>,
+}
+
+/// Describes a feature of a device.
+pub struct FeatureDescription {
+    /// A numeric ID of the feature
+    pub id: u32,
+    /// A description of the feature
+    pub description: String,
+    /// A version number of the feature implementation
+    pub version: String,
+    /// The parameters of the feature
+    pub parameters: Vec<ParameterDescription>,
+}
+
+/// Describes a parameter of a feature.
+pub struct ParameterDescription {
+    /// A numeric ID of the parameter
+    pub id: u32,
+    /// A description of the parameter
+    pub description: String,
+    /// The kind of the value
+    pub kind: u32,
+    /// Whether the parameter can only be read
+    pub read_only: bool,
+    /// The size of a variable-size value, or `None`
+    pub size: Option<u32>,
+    /// The minimum acceptable value, or `None`
+    pub min_value: SoundCoreParamValue,
+    /// The maximum acceptable value, or `None`
+    pub max_value: SoundCoreParamValue,
+    /// The distance between acceptable values, or `None`
+    pub step_size: SoundCoreParamValue,
+    /// Information about the parameter from the catalog
+    pub info: Option<ParameterInfo>,
+}
+
+/// Gets information about a device.
+///
+/// If `device_id` is `None`, the system default output device will be used.
+pub fn device_info<I>(device_id: Option<I>) -> Result<DeviceInfo, Box<dyn Error>>
+where
+    I: Into<PCWSTR>,
+{
+    let endpoint = get_endpoint(device_id)?;
+    let defaults = default_devices(&DeviceEnumerator::new()?)?;
+    DeviceInfo::new(&endpoint, &defaults)
+}
+
+/// Describes the features and parameters of a device, including what the
+/// built-in [`Catalog`](catalog/struct.Catalog.html) knows about them.
+///
+/// If `device_id` is `None`, the system default output device will be used.
+///
+/// # Examples
+///
+/// ```
+/// for feature in describe(None)?.features {
+///     println!("{}", feature.description);
+/// }
+/// ```
+pub fn describe<I>(device_id: Option<I>) -> Result<DeviceDescription, Box<dyn Error>>
+where
+    I: Into<PCWSTR>,
+{
+    let endpoint = get_endpoint(device_id)?;
+    let defaults = default_devices(&DeviceEnumerator::new()?)?;
+    let device = DeviceInfo::new(&endpoint, &defaults)?;
+    let catalog = Catalog::builtin();
+    let model = device
+        .interface
+        .as_deref()
+        .and_then(|interface| catalog.model_for_interface(interface));
+
+    let clsid = endpoint.clsid()?;
+    let core = SoundCore::for_device(&clsid, &device.id)?;
+    let contexts = core.contexts().collect::<Result<Vec<_>, _>>()?;
+    let mut features = Vec::new();
+    for feature in core.features(0) {
+        let feature = feature?;
+        let mut parameters = Vec::new();
+        for parameter in feature.parameters() {
+            let parameter = parameter?;
+            let info = catalog
+                .parameter(model, &feature.description, &parameter.description)
+                .cloned();
+            parameters.push(ParameterDescription {
+                id: parameter.id,
+                read_only: parameter.attributes & 1 != 0,
+                kind: parameter.kind,
+                size: parameter.size,
+                min_value: parameter.min_value,
+                max_value: parameter.max_value,
+                step_size: parameter.step_size,
+                description: parameter.description,
+                info,
+            });
+        }
+        features.push(FeatureDescription {
+            id: feature.id,
+            description: feature.description,
+            version: feature.version,
+            parameters,
+        });
+    }
+
+    Ok(DeviceDescription {
+        model: model.cloned(),
+        contexts,
+        device,
+        features,
+    })
+}
+
+/// Finds the equalizer of a device and captures its current gains.
+///
+/// If `feature` is `None`, the first feature that looks like an equalizer is
+/// used. If `device_id` is `None`, the system default output device will be
+/// used.
+///
+/// # Examples
+///
+/// ```
+/// let preset: EqPreset = "Preamp: -6 dB\nFilter 1: ON PK Fc 100 Hz Gain 3 dB Q 1.41".parse()?;
+/// let equalizer = equalizer(None, None)?;
+/// let mut creative = IndexMap::new();
+/// creative.insert(equalizer.feature.clone(), equalizer.settings(&preset));
+/// let configuration = Configuration {
+///     endpoint: None,
+///     creative: Some(creative),
+///     contexts: None,
+///     endpoints: None,
+/// };
+/// set(None, &configuration, true)?;
+/// ```
+pub fn equalizer<I>(
+    device_id: Option<I>,
+    feature: Option<&str>,
+) -> Result<Equalizer, Box<dyn Error>>
+where
+    I: Into<PCWSTR>,
+{
+    let endpoint = get_endpoint(device_id)?;
+    let id = endpoint.id()?;
+    let clsid = endpoint.clsid()?;
+    let core = SoundCore::for_device(&clsid, &id)?;
+    let features = core.features(0).collect::<Result<Vec<_>, _>>()?;
+    Equalizer::find(&features, feature)
+}
+
+pub(crate) fn get_endpoint<I>(device_id: Option<I>) -> windows::core::Result<Endpoint>
 where
     I: Into<PCWSTR>,
 {
@@ -112,13 +444,42 @@ where
 /// println!("{:?}", dump(None)?);
 /// ```
 pub fn dump<I>(device_id: Option<I>) -> Result<Configuration, Box<dyn Error>>
+where
+    I: Into<PCWSTR>,
+{
+    dump_with_options(device_id, &DumpOptions::default())
+}
+
+/// Captures a snapshot of a device's configuration.
+///
+/// If `device_id` is `None`, the system default output device will be used.
+///
+/// # Examples
+///
+/// ```
+/// let options = DumpOptions {
+///     volume_in_decibels: true,
+///     ..DumpOptions::default()
+/// };
+/// println!("{:?}", dump_with_options(None, &options)?);
+/// ```
+pub fn dump_with_options<I>(
+    device_id: Option<I>,
+    options: &DumpOptions,
+) -> Result<Configuration, Box<dyn Error>>
 where
     I: Into<PCWSTR>,
 {
     let endpoint = get_endpoint(device_id)?;
 
     let endpoint_output = EndpointConfiguration {
-        volume: Some(endpoint.get_volume()?),
+        volume: Some(Setting::Absolute(if options.volume_in_decibels {
+            Volume::Decibels(endpoint.get_volume_db()?)
+        } else {
+            Volume::Scalar(endpoint.get_volume()?)
+        })),
+        mute: Some(endpoint.get_mute()?),
+        default: Vec::new(),
     };
 
     let id = endpoint.id()?;
@@ -143,16 +504,86 @@ where
     );
     let core = SoundCore::for_device(&clsid, &id)?;
 
+    let mut creative_output = None;
+    let mut contexts_output = None;
+    if options.all_contexts {
+        let mut contexts = IndexMap::new();
+        for context in core.contexts() {
+            let context = context?;
+            let context_span =
+                debug_span!("context", id = context.id, description = %context.description);
+            let _context_span = context_span.enter();
+            let output = dump_context(&core, context.id, options)?;
+            if context.id == 0 {
+                creative_output = Some(output);
+            } else {
+                contexts.insert(context_key(&context, options), output);
+            }
+        }
+        contexts_output = Some(contexts);
+    } else if let Some(ref key) = options.context {
+        let context = find_context(&core, key)?;
+        let mut contexts = IndexMap::new();
+        contexts.insert(
+            context_key(&context, options),
+            dump_context(&core, context.id, options)?,
+        );
+        contexts_output = Some(contexts);
+    }
+    if creative_output.is_none() && (contexts_output.is_none() || options.all_contexts) {
+        creative_output = Some(dump_context(&core, 0, options)?);
+    }
+
+    Ok(Configuration {
+        endpoint: Some(endpoint_output),
+        creative: creative_output,
+        contexts: contexts_output,
+        endpoints: None,
+    })
+}
+
+fn context_key(context: &SoundCoreContext, options: &DumpOptions) -> String {
+    ItemKey::format(context.id, &context.description, options.include_ids)
+}
+
+/// Captures the settings of the features of a context.
+fn dump_context(
+    core: &SoundCore,
+    context: u32,
+    options: &DumpOptions,
+) -> Result<CreativeSettings, Box<dyn Error>> {
+    let features = core.features(context).collect::<Result<Vec<_>, _>>()?;
+    let duplicate_features = duplicate_descriptions(features.iter().map(|f| &f.description[..]));
+    for (description, count) in &duplicate_features {
+        warn!(description, count, "Multiple features share a description");
+    }
+
     let mut context_output = IndexMap::new();
-    for feature in core.features(0) {
-        let feature = feature?;
+    for feature in &features {
         let feature_span =
             debug_span!("feature", id = feature.id, description = %feature.description);
         let _feature_span = feature_span.enter();
 
+        let parameters = feature.parameters().collect::<Result<Vec<_>, _>>()?;
+        let duplicate_parameters =
+            duplicate_descriptions(parameters.iter().map(|p| &p.description[..]));
+        for (description, count) in &duplicate_parameters {
+            warn!(
+                description,
+                count, "Multiple parameters share a description"
+            );
+        }
+        let parameter_key = |parameter: &SoundCoreParameter| {
+            ItemKey::format(
+                parameter.id,
+                &parameter.description,
+                options.include_ids
+                    || duplicate_parameters.contains_key(&parameter.description[..]),
+            )
+        };
+
         let mut feature_output = IndexMap::new();
-        for parameter in feature.parameters() {
-            let parameter = parameter?;
+        for parameter in &parameters {
             let parameter_span = debug_span!(
                 "parameter",
                 id = parameter.id,
@@ -177,11 +608,12 @@ where
                             Ok(SoundCoreParamValue::None) => {}
                             Ok(value) => {
                                 debug!(value = ?value, "Got value");
-                                feature_output.insert(parameter.description.clone(), value);
+                                feature_output
+                                    .insert(parameter_key(parameter), Setting::Absolute(value));
                             }
                         }
                     }
-                    0 | 2 | 3 => {
+                    0 | 2 | 3 | 5 => {
                         let value = parameter.get();
                         match value {
                             Err(err) => {
@@ -190,11 +622,11 @@ where
                             Ok(SoundCoreParamValue::None) => {}
                             Ok(value) => {
                                 debug!(value = ?value, "Got value");
-                                feature_output.insert(parameter.description.clone(), value);
+                                feature_output
+                                    .insert(parameter_key(parameter), Setting::Absolute(value));
                             }
                         }
                     }
-                    5 => {}
                     _ => {
                         debug!("Unrecognized kind");
                     }
@@ -203,14 +635,92 @@ where
         }
         // omit feature if no parameters are applicable
         if !feature_output.is_empty() {
-            context_output.insert(feature.description.clone(), feature_output);
+            let include_id =
+                options.include_ids || duplicate_features.contains_key(&feature.description[..]);
+            context_output.insert(
+                ItemKey::format(feature.id, &feature.description, include_id),
+                feature_output,
+            );
         }
     }
 
-    Ok(Configuration {
-        endpoint: Some(endpoint_output),
-        creative: Some(context_output),
-    })
+    Ok(context_output)
+}
+
+/// Finds a context by description or `#id`.
+fn find_context(core: &SoundCore, key: &str) -> Result<SoundCoreContext, Box<dyn Error>> {
+    let key = ItemKey::parse(key);
+    let contexts = core.contexts().collect::<Result<Vec<_>, _>>()?;
+    match key.find("context", &contexts, |c| c.id, |c| &c.description)? {
+        Some(index) => Ok(contexts.into_iter().nth(index).unwrap()),
+        // the device may not list every context it supports
+        None => match key {
+            ItemKey::Id(id, _) => Ok(core.context_info(id)?),
+            ItemKey::Description(description) => Err(Box::new(UnknownContextError {
+                description: description.to_owned(),
+                known: contexts.into_iter().map(|c| c.description).collect(),
+            })),
+        },
+    }
+}
+
+#[derive(Debug)]
+struct UnknownContextError {
+    description: String,
+    known: Vec<String>,
+}
+
+impl fmt::Display for UnknownContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Could not find context {:?}. Known contexts: {}",
+            self.description,
+            self.known.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownContextError {}
+
+/// Describes the changes made by applying a configuration.
+#[derive(Debug, Default)]
+pub struct SetReport {
+    /// The ids of the devices that were enabled
+    pub enabled: Vec<String>,
+    /// The ids of the devices that were disabled
+    pub disabled: Vec<String>,
+    /// The roles the device became the default for
+    pub default_for: Vec<DeviceRole>,
+}
+
+impl SetReport {
+    /// Checks whether no changes are reported.
+    pub fn is_empty(&self) -> bool {
+        self.enabled.is_empty() && self.disabled.is_empty() && self.default_for.is_empty()
+    }
+}
+
+/// Describes a failure of [`set`](fn.set.html) after other devices were
+/// already enabled or disabled.
+#[derive(Debug)]
+pub struct SetError {
+    /// The changes made before the failure
+    pub report: SetReport,
+    /// The cause of the failure
+    pub error: Box<dyn Error>,
+}
+
+impl fmt::Display for SetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl Error for SetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.error)
+    }
 }
 
 /// Applies a set of configuration values to a device.
@@ -221,15 +731,23 @@ where
 /// and unmuted at the end. In any case, the device will not be unmuted if it
 /// was already muted before calling this function.
 ///
+/// Devices that were enabled or disabled, and the roles the device became
+/// the default for, are reported in the result. If applying the rest of the
+/// configuration fails after devices were enabled or disabled, the error is
+/// a [`SetError`](struct.SetError.html) that reports those changes.
+///
 /// # Examples
 ///
 /// ```
-/// let mut creative = BTreeMap::<String, BTreeMap<String, Value>>::new();
-/// let mut device_control = BTreeMap::<String, Value>::new();
-/// device_control.insert("SelectOutput".to_string(), Value::Integer(1));
+/// let mut creative = IndexMap::<String, IndexMap<String, Setting<SoundCoreParamValue>>>::new();
+/// let mut device_control = IndexMap::<String, Setting<SoundCoreParamValue>>::new();
+/// device_control.insert("SelectOutput".to_string(), Setting::Absolute(SoundCoreParamValue::U32(1)));
+/// creative.insert("Device Control".to_string(), device_control);
 /// let configuration = Configuration {
 ///     endpoint: None,
-///     creative,
+///     creative: Some(creative),
+///     contexts: None,
+///     endpoints: None,
 /// };
 /// set(None, &configuration, true);
 /// ```
@@ -237,21 +755,98 @@ pub fn set<I>(
     device_id: Option<I>,
     configuration: &Configuration,
     mute: bool,
+) -> Result<SetReport, Box<dyn Error>>
+where
+    I: Into<PCWSTR>,
+{
+    let mut report = SetReport::default();
+    match set_with_report(device_id, configuration, mute, &mut report) {
+        Ok(()) => Ok(report),
+        Err(error) if report.is_empty() => Err(error),
+        Err(error) => Err(Box::new(SetError { report, error })),
+    }
+}
+
+fn set_with_report<I>(
+    device_id: Option<I>,
+    configuration: &Configuration,
+    mute: bool,
+    report: &mut SetReport,
 ) -> Result<(), Box<dyn Error>>
 where
     I: Into<PCWSTR>,
 {
-    let endpoint = get_endpoint(device_id)?;
-    let mute_unmute = mute && !endpoint.get_mute()?;
-    if mute_unmute {
-        endpoint.set_mute(true)?;
+    if let Some(ref endpoints) = configuration.endpoints {
+        set_endpoints_enabled(endpoints, report)?;
     }
-    let result = set_internal(configuration, &endpoint);
-    if mute_unmute {
-        endpoint.set_mute(false)?;
+
+    let endpoint = get_endpoint(device_id)?;
+    let (requested_mute, roles) = match configuration.endpoint {
+        Some(ref endpoint) => (endpoint.mute, &endpoint.default[..]),
+        None => (None, &[][..]),
+    };
+    report.default_for = configure_and_make_default(
+        &SystemDefaults::new()?,
+        &endpoint,
+        mute,
+        requested_mute,
+        roles,
+        || set_internal(configuration, &endpoint),
+    )?;
+    Ok(())
+}
+
+fn set_endpoints_enabled(
+    endpoints: &IndexMap<String, bool>,
+    report: &mut SetReport,
+) -> Result<(), Box<dyn Error>> {
+    let enumerator = DeviceEnumerator::new()?;
+    let policy = PolicyConfig::new()?;
+    for (selector, &enabled) in endpoints {
+        let id = selector
+            .parse::<DeviceSelector>()?
+            .resolve_with(None, true)?;
+        let state = enumerator
+            .get_endpoint(&HSTRING::from(id.as_str()))?
+            .state()?;
+        let currently_enabled = state != DeviceState::Disabled;
+        if currently_enabled == enabled {
+            debug!(
+                id = id.as_str(),
+                state = state.name(),
+                "Device is unchanged"
+            );
+            continue;
+        }
+        info!(id = id.as_str(), enabled, "Changing device");
+        policy.set_endpoint_visibility(&id, enabled)?;
+        if enabled {
+            report.enabled.push(id);
+        } else {
+            report.disabled.push(id);
+        }
     }
+    Ok(())
+}
 
-    result
+/// Makes a device the Windows default device for some roles.
+///
+/// If `device_id` is None, the system default output device will be used.
+/// Returns the roles the device was not already the default for.
+pub fn set_default<I>(
+    device_id: Option<I>,
+    roles: &[DeviceRole],
+) -> Result<Vec<DeviceRole>, Box<dyn Error>>
+where
+    I: Into<PCWSTR>,
+{
+    let endpoint = get_endpoint(device_id)?;
+    make_default(
+        &SystemDefaults::new()?,
+        &endpoint.id()?,
+        endpoint.flow()?,
+        roles,
+    )
 }
 
 /// Gets the sequence of events for a device.
@@ -277,43 +872,244 @@ where
     Ok(core.events()?)
 }
 
-/// Either a SoundCoreEvent or a VolumeNotification.
+/// A SoundCoreEvent, a VolumeNotification, or a change to the audio devices
+/// of the system.
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum SoundCoreOrVolumeEvent {
     /// A SoundCoreEvent.
     SoundCore(SoundCoreEvent),
     /// A VolumeNotification.
     Volume(VolumeNotification),
+    /// A device was added.
+    DeviceAdded {
+        /// The id of the device
+        id: String,
+    },
+    /// A device was removed.
+    DeviceRemoved {
+        /// The id of the device
+        id: String,
+    },
+    /// A device was enabled, disabled, unplugged, or plugged in.
+    DeviceStateChanged {
+        /// The id of the device
+        id: String,
+        /// The new state of the device
+        state: DeviceState,
+    },
+    /// The default device for a role changed.
+    DefaultDeviceChanged {
+        /// Whether the default output or input changed
+        flow: DataFlow,
+        /// The role the device is now the default for
+        role: DeviceRole,
+        /// The id of the new default device, if there is one
+        id: Option<String>,
+    },
+    /// A property of a device changed.
+    PropertyChanged {
+        /// The id of the device
+        id: String,
+        /// The property that changed
+        key: PROPERTYKEY,
+    },
+}
+
+impl From<DeviceNotification> for SoundCoreOrVolumeEvent {
+    fn from(notification: DeviceNotification) -> Self {
+        match notification {
+            DeviceNotification::Added { id } => SoundCoreOrVolumeEvent::DeviceAdded { id },
+            DeviceNotification::Removed { id } => SoundCoreOrVolumeEvent::DeviceRemoved { id },
+            DeviceNotification::StateChanged { id, state } => {
+                SoundCoreOrVolumeEvent::DeviceStateChanged { id, state }
+            }
+            DeviceNotification::DefaultChanged { flow, role, id } => {
+                SoundCoreOrVolumeEvent::DefaultDeviceChanged { flow, role, id }
+            }
+            DeviceNotification::PropertyChanged { id, key } => {
+                SoundCoreOrVolumeEvent::PropertyChanged { id, key }
+            }
+        }
+    }
+}
+
+/// Identifies the events of a `SoundCoreOrVolumeEvent` stream that replace
+/// each other.
+#[derive(PartialEq)]
+enum EventKey {
+    Parameter((u32, u32)),
+    Volume,
+}
+
+impl Coalesce for SoundCoreOrVolumeEvent {
+    type Key = EventKey;
+
+    fn coalesce_key(&self) -> Option<Self::Key> {
+        match self {
+            SoundCoreOrVolumeEvent::SoundCore(event) => {
+                event.coalesce_key().map(EventKey::Parameter)
+            }
+            SoundCoreOrVolumeEvent::Volume(event) => {
+                event.coalesce_key().map(|()| EventKey::Volume)
+            }
+            _ => None,
+        }
+    }
+
+    fn coalesce(self, later: Self) -> Option<Self> {
+        match (self, later) {
+            (
+                SoundCoreOrVolumeEvent::SoundCore(earlier),
+                SoundCoreOrVolumeEvent::SoundCore(later),
+            ) => earlier
+                .coalesce(later)
+                .map(SoundCoreOrVolumeEvent::SoundCore),
+            (_, later) => Some(later),
+        }
+    }
+}
+
+/// Reads values periodically for a `SoundCoreAndVolumeEvents` stream.
+struct Poller {
+    interval: Duration,
+    /// The feature and parameter ids to read
+    parameters: Vec<(u32, u32)>,
+    volume: bool,
+    next: Instant,
+    /// Wakes the stream when it is time to read the values again
+    timer: Timer,
+    /// The context the parameter values were last seeded in
+    context: u32,
+}
+
+impl Poller {
+    /// Reads the current values as a baseline, without reporting them.
+    fn seed(&mut self, events: &mut SoundCoreEvents) {
+        self.context = events.context();
+        for &(feature, parameter) in &self.parameters {
+            if let Err(error) = events.seed(feature, parameter) {
+                debug!(feature, parameter, error = %error, "Could not read polled parameter");
+            }
+        }
+    }
 }
 
 struct SoundCoreAndVolumeEvents {
     sound_core: Fuse<SoundCoreEvents>,
     volume: Fuse<VolumeEvents>,
+    devices: Fuse<DeviceEvents>,
+    poller: Option<Poller>,
+    /// Changes found by polling that have not been reported yet
+    polled: VecDeque<SoundCoreOrVolumeEvent>,
+}
+
+impl SoundCoreAndVolumeEvents {
+    /// Reads the polled values if it is time to, and arms a timer for the
+    /// next time.
+    fn poll_values(&mut self, cx: &mut Context) {
+        let poller = match self.poller {
+            Some(ref mut poller) => poller,
+            None => return,
+        };
+        let now = Instant::now();
+        if now >= poller.next {
+            poller.next = now + poller.interval;
+            let events = self.sound_core.get_mut();
+            if events.context() != poller.context {
+                // the values of the new context are not changes
+                poller.seed(events);
+            }
+            for &(feature, parameter) in &poller.parameters {
+                match self.sound_core.get_mut().param_change(feature, parameter) {
+                    Ok(Some(event)) => self
+                        .polled
+                        .push_back(SoundCoreOrVolumeEvent::SoundCore(event)),
+                    Ok(None) => {}
+                    // the parameter may not exist in the current context
+                    Err(error) => {
+                        debug!(feature, parameter, error = %error, "Could not poll parameter")
+                    }
+                }
+            }
+            if poller.volume {
+                match self.volume.get_mut().poll_volume() {
+                    Ok(Some(event)) => self.polled.push_back(SoundCoreOrVolumeEvent::Volume(event)),
+                    Ok(None) => {}
+                    Err(error) => debug!(error = %error, "Could not poll volume"),
+                }
+            }
+        }
+        poller.timer.wake_at(poller.next, cx.waker());
+    }
 }
 
 impl Stream for SoundCoreAndVolumeEvents {
     type Item = windows::core::Result<SoundCoreOrVolumeEvent>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        if let Poll::Ready(Some(item)) = Pin::new(&mut self.sound_core).poll_next(cx) {
+        self.poll_values(cx);
+        if let Some(event) = self.polled.pop_front() {
+            Poll::Ready(Some(Ok(event)))
+        } else if let Poll::Ready(Some(item)) = Pin::new(&mut self.sound_core).poll_next(cx) {
             Poll::Ready(Some(match item {
                 Ok(item) => Ok(SoundCoreOrVolumeEvent::SoundCore(item)),
                 Err(err) => Err(err),
             }))
         } else if let Poll::Ready(Some(item)) = Pin::new(&mut self.volume).poll_next(cx) {
             Poll::Ready(Some(Ok(SoundCoreOrVolumeEvent::Volume(item))))
+        } else if let Poll::Ready(Some(item)) = Pin::new(&mut self.devices).poll_next(cx) {
+            Poll::Ready(Some(Ok(item.into())))
         } else {
             Poll::Pending
         }
     }
 }
 
-/// Iterates over volume change events and also events produced through the
-/// SoundCore API.
+/// Iterates over volume change events, events produced through the SoundCore
+/// API, and changes to the audio devices of the system.
 ///
-/// This iterator will block until the next event is available.
+/// This iterator will block until the next event is available. Use
+/// [`try_next`](#method.try_next) or [`next_timeout`](#method.next_timeout)
+/// to wait for a limited time.
 pub struct SoundCoreAndVolumeEventIterator {
-    inner: ComEventIterator<SoundCoreAndVolumeEvents>,
+    inner: ComEventIterator<Debounce<SoundCoreAndVolumeEvents>>,
+}
+
+impl SoundCoreAndVolumeEventIterator {
+    /// Gets the next event if one is available, without blocking.
+    ///
+    /// Returns `Poll::Pending` if there is no event yet, and
+    /// `Poll::Ready(None)` if the iterator has ended or was cancelled.
+    pub fn try_next(&mut self) -> Poll<Option<windows::core::Result<SoundCoreOrVolumeEvent>>> {
+        self.inner.try_next()
+    }
+
+    /// Waits up to `timeout` for the next event.
+    ///
+    /// Returns `Poll::Pending` if the timeout passed, and
+    /// `Poll::Ready(None)` if the iterator has ended or was cancelled.
+    pub fn next_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Poll<Option<windows::core::Result<SoundCoreOrVolumeEvent>>> {
+        self.inner.next_timeout(timeout)
+    }
+
+    /// Gets a handle that stops the iterator from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.inner.cancel_handle()
+    }
+
+    /// Gets an event handle that is signaled when an event may be available.
+    ///
+    /// This allows waiting for events together with other handles, such as
+    /// in `MsgWaitForMultipleObjects`, and then calling
+    /// [`try_next`](#method.try_next). The handle is an auto-reset event,
+    /// and is closed when the iterator is dropped.
+    pub fn event_handle(&self) -> HANDLE {
+        self.inner.event_handle()
+    }
 }
 
 impl Iterator for SoundCoreAndVolumeEventIterator {
@@ -328,6 +1124,9 @@ impl Iterator for SoundCoreAndVolumeEventIterator {
 ///
 /// If `device_id` is None, the system default output device will be used.
 ///
+/// Devices being added, removed, enabled, or disabled, and changes of the
+/// default devices, are reported for every device, not only this one.
+///
 /// # Examples
 ///
 /// ```
@@ -338,6 +1137,69 @@ impl Iterator for SoundCoreAndVolumeEventIterator {
 pub fn watch_with_volume<I>(
     device_id: Option<I>,
 ) -> Result<SoundCoreAndVolumeEventIterator, Box<dyn Error>>
+where
+    I: Into<PCWSTR>,
+{
+    watch_with_options(device_id, &WatchOptions::default())
+}
+
+/// Controls how events are reported.
+#[derive(Clone, Debug, Default)]
+pub struct WatchOptions {
+    /// Coalesces changes of the same parameter, or of the volume, that
+    /// follow each other within this window into a single event with the
+    /// final value.
+    ///
+    /// Events are delayed by up to this long. A parameter that changes and
+    /// then changes back within the window is not reported if its value
+    /// before the change was known, i.e. the first reported change had an
+    /// `old` value. Other events end the window early, so the events are
+    /// reported in the order they happened.
+    pub debounce: Option<Duration>,
+    /// Selects the SoundCore events to register for
+    pub event_mask: EventMask,
+    /// Reads parameters and the volume periodically, to report changes that
+    /// the device does not send notifications for.
+    pub poll: Option<PollOptions>,
+}
+
+/// Selects the values read periodically while watching.
+///
+/// A changed value is reported in the same way as a notification would
+/// report it, and a change that is both polled and notified is reported
+/// only once. The values read when watching starts, or when the device
+/// switches to another context, are not reported.
+#[derive(Clone, Debug)]
+pub struct PollOptions {
+    /// How often to read the values
+    pub interval: Duration,
+    /// Reads the parameters of the features matching this pattern, or of
+    /// every feature
+    pub feature: Option<Pattern>,
+    /// Reads the parameters matching this pattern, or every parameter
+    pub parameter: Option<Pattern>,
+    /// Reads the volume and mute state of the endpoint
+    pub volume: bool,
+}
+
+/// Gets the sequence of events for a device, like
+/// [`watch_with_volume`](fn.watch_with_volume.html), with options.
+///
+/// # Examples
+///
+/// ```
+/// let options = WatchOptions {
+///     debounce: Some(Duration::from_millis(200)),
+///     ..Default::default()
+/// };
+/// for event in watch_with_options(None, &options) {
+///     println!("{:?}", event);
+/// }
+/// ```
+pub fn watch_with_options<I>(
+    device_id: Option<I>,
+    options: &WatchOptions,
+) -> Result<SoundCoreAndVolumeEventIterator, Box<dyn Error>>
 where
     I: Into<PCWSTR>,
 {
@@ -346,17 +1208,70 @@ where
     let clsid = endpoint.clsid()?;
     let core = SoundCore::for_device(&clsid, &id)?;
 
-    let core_events = core.event_stream()?;
+    let mut core_events = core.event_stream(options.event_mask)?;
     let volume_events = endpoint.event_stream()?;
+    let device_events = DeviceEnumerator::new()?.event_stream()?;
+    let poller = match options.poll {
+        Some(ref poll) => {
+            let mut poller = Poller {
+                interval: poll.interval,
+                parameters: polled_parameters(&core, poll)?,
+                volume: poll.volume,
+                next: Instant::now(),
+                timer: Timer::default(),
+                context: 0,
+            };
+            poller.seed(&mut core_events);
+            Some(poller)
+        }
+        None => None,
+    };
 
     Ok(SoundCoreAndVolumeEventIterator {
-        inner: ComEventIterator::new(SoundCoreAndVolumeEvents {
-            sound_core: core_events.fuse(),
-            volume: volume_events.fuse(),
-        }),
+        inner: ComEventIterator::new(
+            SoundCoreAndVolumeEvents {
+                sound_core: core_events.fuse(),
+                volume: volume_events.fuse(),
+                devices: device_events.fuse(),
+                poller,
+                polled: VecDeque::new(),
+            }
+            .debounce(options.debounce.unwrap_or_default()),
+        ),
     })
 }
 
+/// Finds the ids of the parameters to poll in the current context.
+fn polled_parameters(
+    core: &SoundCore,
+    options: &PollOptions,
+) -> Result<Vec<(u32, u32)>, Box<dyn Error>> {
+    let matches = |pattern: &Option<Pattern>, text: &str| {
+        pattern
+            .as_ref()
+            .map_or(true, |pattern| pattern.matches(text))
+    };
+    let context = core.current_context()?;
+    let mut parameters = Vec::new();
+    for feature in core.features(context) {
+        let feature = feature?;
+        if !matches(&options.feature, &feature.description) {
+            continue;
+        }
+        for parameter in feature.parameters() {
+            let parameter = parameter?;
+            // only the kinds that can be read
+            if matches!(parameter.kind, 0 | 1 | 2 | 3 | 5)
+                && matches(&options.parameter, &parameter.description)
+            {
+                parameters.push((feature.id, parameter.id));
+            }
+        }
+    }
+    debug!(count = parameters.len(), "Polling parameters");
+    Ok(parameters)
+}
+
 #[derive(Debug)]
 struct UnsupportedValueError {
     feature: String,
@@ -401,32 +1316,177 @@ fn coerce_soundcore(
         (&SoundCoreParamValue::U32(i), 3) if i <= i32::max_value() as u32 => {
             Ok(SoundCoreParamValue::I32(i as i32))
         }
-        _ => {
-            let actual = match *value {
-                SoundCoreParamValue::Float(_) => "float",
-                SoundCoreParamValue::Bool(_) => "bool",
-                SoundCoreParamValue::I32(_) => "int",
-                SoundCoreParamValue::U32(_) => "uint",
-                SoundCoreParamValue::None => "<unsupported>",
+        (SoundCoreParamValue::Bytes(data), 5) => Ok(SoundCoreParamValue::Bytes(data.clone())),
+        _ => Err(UnsupportedValueError {
+            feature: feature.description.to_owned(),
+            parameter: parameter.description.to_owned(),
+            expected: kind_name(parameter.kind),
+            actual: value_kind_name(value),
+        }),
+    }
+}
+
+fn kind_name(kind: u32) -> &'static str {
+    match kind {
+        0 => "float",
+        1 => "bool",
+        2 => "uint",
+        3 => "int",
+        5 => "bytes",
+        _ => "<unsupported>",
+    }
+}
+
+fn value_kind_name(value: &SoundCoreParamValue) -> &'static str {
+    match *value {
+        SoundCoreParamValue::Float(_) => "float",
+        SoundCoreParamValue::Bool(_) => "bool",
+        SoundCoreParamValue::I32(_) => "int",
+        SoundCoreParamValue::U32(_) => "uint",
+        SoundCoreParamValue::Bytes(_) => "bytes",
+        SoundCoreParamValue::None => "<unsupported>",
+    }
+}
+
+fn numeric_value(value: &SoundCoreParamValue) -> Option<f64> {
+    match *value {
+        SoundCoreParamValue::Float(f) => Some(f64::from(f)),
+        SoundCoreParamValue::U32(i) => Some(f64::from(i)),
+        SoundCoreParamValue::I32(i) => Some(f64::from(i)),
+        _ => None,
+    }
+}
+
+/// Clamps a value to a range and rounds it to a multiple of the step size
+/// above the minimum.
+fn snap_to_range(value: f64, min: Option<f64>, max: Option<f64>, step: Option<f64>) -> f64 {
+    let clamp = |value: f64| {
+        let value = min.map_or(value, |min| value.max(min));
+        max.map_or(value, |max| value.min(max))
+    };
+    let mut value = clamp(value);
+    if let Some(step) = step.filter(|step| *step > 0.0) {
+        let base = min.unwrap_or(0.0);
+        // snapping may land just outside the range, so clamp again
+        value = clamp(base + ((value - base) / step).round() * step);
+    }
+    value
+}
+
+fn adjust_soundcore(
+    feature: &SoundCoreFeature,
+    parameter: &SoundCoreParameter,
+    delta: &SoundCoreParamValue,
+) -> Result<SoundCoreParamValue, Box<dyn Error>> {
+    let current = parameter.get()?;
+    let unsupported = |expected, actual| UnsupportedValueError {
+        feature: feature.description.to_owned(),
+        parameter: parameter.description.to_owned(),
+        expected,
+        actual,
+    };
+    let current_value = numeric_value(&current)
+        .ok_or_else(|| unsupported("a numeric parameter", value_kind_name(&current)))?;
+    let delta_value =
+        numeric_value(delta).ok_or_else(|| unsupported("a number", value_kind_name(delta)))?;
+
+    let value = snap_to_range(
+        current_value + delta_value,
+        numeric_value(&parameter.min_value),
+        numeric_value(&parameter.max_value),
+        numeric_value(&parameter.step_size),
+    );
+    debug!(current = ?current, delta = ?delta, value, "Adjusting value");
+
+    Ok(match parameter.kind {
+        0 => SoundCoreParamValue::Float(value as f32),
+        2 => SoundCoreParamValue::U32(value.round() as u32),
+        3 => SoundCoreParamValue::I32(value.round() as i32),
+        kind => return Err(Box::new(unsupported(kind_name(kind), "a relative value"))),
+    })
+}
+
+/// The parameters of a feature that a configuration changes.
+struct FeaturePlan<'a> {
+    feature: &'a SoundCoreFeature,
+    parameters: Vec<SoundCoreParameter>,
+    /// The settings for each parameter, in the order they are applied
+    settings: Vec<Vec<&'a Setting<SoundCoreParamValue>>>,
+}
+
+/// Warns about parameters that will not take effect because a parameter they
+/// depend on has an incompatible value.
+fn check_requirements(
+    catalog: &Catalog,
+    model: Option<&Model>,
+    features: &[SoundCoreFeature],
+    plan: &[FeaturePlan],
+) {
+    let planned_setting = |feature: &str, parameter: &str| {
+        plan.iter()
+            .filter(|p| p.feature.description == feature)
+            .flat_map(|p| p.parameters.iter().zip(&p.settings))
+            .filter(|(p, _)| p.description == parameter)
+            .flat_map(|(_, settings)| settings.iter())
+            .last()
+    };
+    for FeaturePlan {
+        feature,
+        parameters,
+        settings,
+    } in plan
+    {
+        for (parameter, settings) in parameters.iter().zip(settings) {
+            if settings.is_empty() {
+                continue;
+            }
+            let info = match catalog.parameter(model, &feature.description, &parameter.description)
+            {
+                Some(info) => info,
+                None => continue,
             };
-            Err(UnsupportedValueError {
-                feature: feature.description.to_owned(),
-                parameter: parameter.description.to_owned(),
-                expected: match parameter.kind {
-                    0 => "float",
-                    1 => "bool",
-                    2 => "uint",
-                    3 => "int",
-                    _ => "<unsupported>",
-                },
-                actual,
-            })
+            for requirement in &info.requires {
+                let value = match planned_setting(&requirement.feature, &requirement.parameter) {
+                    Some(Setting::Absolute(value)) => Some(value.clone()),
+                    // the result of an adjustment is not known yet
+                    Some(Setting::Relative(_)) => continue,
+                    None => current_value(features, &requirement.feature, &requirement.parameter),
+                };
+                if let Some(value) = value {
+                    if !requirement.values.iter().any(|v| v.matches(&value)) {
+                        warn!(
+                            feature = feature.description,
+                            parameter = parameter.description,
+                            requires = %requirement,
+                            actual = %value,
+                            reason = requirement.reason.as_deref().unwrap_or_default(),
+                            "Parameter may have no effect",
+                        );
+                    }
+                }
+            }
         }
     }
 }
 
+fn current_value(
+    features: &[SoundCoreFeature],
+    feature: &str,
+    parameter: &str,
+) -> Option<SoundCoreParamValue> {
+    let feature = features.iter().find(|f| f.description == feature)?;
+    let parameter = feature
+        .parameters()
+        .filter_map(Result::ok)
+        .find(|p| p.description == parameter)?;
+    match parameter.get() {
+        Ok(SoundCoreParamValue::None) | Err(_) => None,
+        Ok(value) => Some(value),
+    }
+}
+
 fn set_internal(configuration: &Configuration, endpoint: &Endpoint) -> Result<(), Box<dyn Error>> {
-    if let Some(ref creative) = configuration.creative {
+    if configuration.creative.is_some() || configuration.contexts.is_some() {
         let id = endpoint.id()?;
         debug!(endpoint_id = id, "Found device");
         let clsid = endpoint.clsid()?;
@@ -449,57 +1509,149 @@ fn set_internal(configuration: &Configuration, endpoint: &Endpoint) -> Result<()
         );
         let core = SoundCore::for_device(&clsid, &id)?;
 
-        let mut unhandled_feature_names = BTreeSet::<&str>::new();
-        for (key, _) in creative.iter() {
-            unhandled_feature_names.insert(key);
+        let catalog = Catalog::builtin();
+        let interface = endpoint.interface().ok();
+        let model = interface
+            .as_deref()
+            .and_then(|interface| catalog.model_for_interface(interface));
+
+        if let Some(ref creative) = configuration.creative {
+            set_creative(&core, &catalog, model, 0, creative)?;
+        }
+        if let Some(ref contexts) = configuration.contexts {
+            for (key, creative) in contexts {
+                let context = find_context(&core, key)?;
+                let context_span =
+                    debug_span!("context", id = context.id, description = %context.description);
+                let _context_span = context_span.enter();
+                set_creative(&core, &catalog, model, context.id, creative)?;
+            }
+        }
+    }
+    if let Some(ref endpoint_config) = configuration.endpoint {
+        if let Some(v) = endpoint_config.volume {
+            set_volume(endpoint, v)?;
         }
+    }
+    Ok(())
+}
 
-        for feature in core.features(0) {
-            let feature = feature?;
-            let feature_span =
-                trace_span!("Looking for feature settings...", feature = %feature.description);
-            let _feature_span = feature_span.enter();
-            if let Some(feature_table) = creative.get(&feature.description) {
-                unhandled_feature_names.remove(&feature.description[..]);
-                let mut unhandled_parameter_names = BTreeSet::<&str>::new();
-                for (key, _) in feature_table.iter() {
-                    unhandled_parameter_names.insert(key);
-                }
+/// Applies settings to the features of a context.
+fn set_creative(
+    core: &SoundCore,
+    catalog: &Catalog,
+    model: Option<&Model>,
+    context: u32,
+    creative: &CreativeSettings,
+) -> Result<(), Box<dyn Error>> {
+    let features = core.features(context).collect::<Result<Vec<_>, _>>()?;
+    let mut feature_tables = vec![Vec::new(); features.len()];
+    for (key, table) in creative {
+        match ItemKey::parse(key).find("feature", &features, |f| f.id, |f| &f.description)? {
+            Some(index) => feature_tables[index].push(table),
+            None => warn!(feature = %key, "Could not find feature"),
+        }
+    }
 
-                for parameter in feature.parameters() {
-                    let mut parameter = parameter?;
-                    let parameter_span = trace_span!("Looking for parameter settings...", parameter = %parameter.description);
-                    let _parameter_span = parameter_span.enter();
-                    if let Some(value) = feature_table.get(&parameter.description) {
-                        unhandled_parameter_names.remove(&parameter.description[..]);
-                        let value = &coerce_soundcore(&feature, &parameter, value)?;
-                        if let Err(error) = parameter.set(value) {
-                            error!(
-                                feature = feature.description,
-                                parameter = parameter.description,
-                                error = %error,
-                                "Could not set parameter",
-                            );
-                        }
-                    }
-                }
-                for unhandled in unhandled_parameter_names {
-                    warn!(
+    // resolve every key before changing anything
+    let mut plan = Vec::new();
+    for (feature, tables) in features.iter().zip(feature_tables) {
+        if tables.is_empty() {
+            continue;
+        }
+        let parameters = feature.parameters().collect::<Result<Vec<_>, _>>()?;
+        let mut settings = vec![Vec::new(); parameters.len()];
+        for (key, value) in tables.into_iter().flatten() {
+            match ItemKey::parse(key).find(
+                "parameter",
+                &parameters,
+                |p| p.id,
+                |p| &p.description,
+            )? {
+                Some(index) => settings[index].push(value),
+                None => warn!(
+                    feature = feature.description,
+                    parameter = %key,
+                    "Could not find parameter",
+                ),
+            }
+        }
+        plan.push(FeaturePlan {
+            feature,
+            parameters,
+            settings,
+        });
+    }
+
+    check_requirements(catalog, model, &features, &plan);
+
+    for FeaturePlan {
+        feature,
+        mut parameters,
+        settings,
+    } in plan
+    {
+        let feature_span =
+            trace_span!("Looking for feature settings...", feature = %feature.description);
+        let _feature_span = feature_span.enter();
+
+        for (parameter, values) in parameters.iter_mut().zip(settings) {
+            let parameter_span = trace_span!("Looking for parameter settings...", parameter = %parameter.description);
+            let _parameter_span = parameter_span.enter();
+            for value in values {
+                let value = &match value {
+                    Setting::Absolute(value) => coerce_soundcore(feature, parameter, value)?,
+                    Setting::Relative(delta) => adjust_soundcore(feature, parameter, delta)?,
+                };
+                if let Err(error) = parameter.set(value) {
+                    error!(
                         feature = feature.description,
-                        parameter = unhandled,
-                        "Could not find parameter",
+                        parameter = parameter.description,
+                        error = %error,
+                        "Could not set parameter",
                     );
                 }
             }
         }
-        for unhandled in unhandled_feature_names {
-            warn!(feature = unhandled, "Could not find feature");
-        }
     }
-    if let Some(ref endpoint_config) = configuration.endpoint {
-        if let Some(v) = endpoint_config.volume {
+    Ok(())
+}
+
+fn set_volume(endpoint: &Endpoint, volume: Setting<Volume>) -> Result<(), Box<dyn Error>> {
+    let volume = match volume {
+        Setting::Absolute(volume) => volume,
+        Setting::Relative(Volume::Scalar(delta)) => {
+            let v = (endpoint.get_volume()? + delta).clamp(0.0, 1.0);
+            Volume::Scalar(v)
+        }
+        Setting::Relative(Volume::Decibels(delta)) => {
+            let range = endpoint.get_volume_range()?;
+            let v = (endpoint.get_volume_db()? + delta).clamp(range.min, range.max);
+            Volume::Decibels(v)
+        }
+    };
+    match volume {
+        Volume::Scalar(v) => {
+            if !(0.0..=1.0).contains(&v) {
+                return Err(Box::new(VolumeOutOfRangeError {
+                    volume,
+                    min: Volume::Scalar(0.0),
+                    max: Volume::Scalar(1.0),
+                }));
+            }
             endpoint.set_volume(v)?;
         }
+        Volume::Decibels(v) => {
+            let range = endpoint.get_volume_range()?;
+            if !(range.min..=range.max).contains(&v) {
+                return Err(Box::new(VolumeOutOfRangeError {
+                    volume,
+                    min: Volume::Decibels(range.min),
+                    max: Volume::Decibels(range.max),
+                }));
+            }
+            endpoint.set_volume_db(v)?;
+        }
     }
     Ok(())
 }
@@ -5,7 +5,9 @@ use clap::Command;
 use clap::{Arg, ArgMatches};
 
 use indexmap::IndexMap;
-use tracing::{debug, error};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{debug, error, warn};
 use tracing_subscriber::filter::EnvFilter;
 use tracing_subscriber::fmt::format::FmtSpan;
 use windows::core::HSTRING;
@@ -19,12 +21,22 @@ use std::io::prelude::*;
 use std::io::BufReader;
 use std::iter::IntoIterator;
 use std::mem;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use toml::value::Value;
-
-use sbz_switch::soundcore::SoundCoreParamValue;
-use sbz_switch::{Configuration, DeviceInfo, EndpointConfiguration};
+use sbz_switch::alias::{extract_aliases, insert_alias, Alias, AliasError};
+use sbz_switch::catalog::{Catalog, CatalogValue, Model};
+use sbz_switch::ctsndcr::EventInfo;
+use sbz_switch::eq::EqPreset;
+use sbz_switch::media::{ClsidSource, DataFlow, DeviceRole};
+use sbz_switch::selector::{DeviceAliases, Pattern};
+use sbz_switch::soundcore::{EventMask, ParseParamValueError, SoundCoreEvent, SoundCoreParamValue};
+use sbz_switch::{
+    Configuration, DeviceInfo, DumpOptions, EndpointConfiguration, ItemKey, ListOptions,
+    ParameterDescription, ParseVolumeError, PollOptions, SetError, SetReport, Setting,
+    SoundCoreOrVolumeEvent, Volume, WatchOptions,
+};
 
 fn main() {
     std::process::exit(run());
@@ -34,28 +46,56 @@ fn run() -> i32 {
     let device_arg = Arg::new("device")
         .short('d')
         .long("device")
-        .value_name("DEVICE_ID")
+        .value_name("DEVICE")
         .allow_invalid_utf8(true)
-        .help("Specify the device to act on (get id from list-devices)");
+        .help("Specify the device to act on by id (get id from list-devices), alias, or selector (name:PATTERN, interface:PATTERN, or role:ROLE)");
+    let capture_arg = Arg::new("capture")
+        .long("capture")
+        .help("Acts on an input device, such as a microphone, instead of an output");
+    let context_arg = Arg::new("context").long("context").value_name("CONTEXT");
     let format_arg = Arg::new("format")
         .short('f')
         .value_name("FORMAT")
-        .possible_values(&["toml", "json", "yaml"])
-        .default_value("toml");
-    let input_format_arg = format_arg.clone().help("Select the input format");
-    let output_format_arg = format_arg.clone().help("Select the output format");
+        .possible_values(FORMAT_NAMES)
+        .ignore_case(true);
+    let input_format_arg = format_arg
+        .clone()
+        .help("Select the input format (detected from the file name or contents by default)");
+    let output_format_arg = format_arg
+        .clone()
+        .help("Select the output format (detected from the file name or toml by default)");
+    let eq_feature_arg = Arg::new("feature")
+        .long("feature")
+        .value_name("FEATURE")
+        .help(
+            "Selects the equalizer feature by description or #id (found automatically by default)",
+        );
     let matches = clap::command!()
         .allow_negative_numbers(true)
         .subcommand_required(true)
         .subcommand(
             Command::new("list-devices")
                 .about("Prints out the names and IDs of available devices")
-                .arg(output_format_arg.clone()),
+                .arg(output_format_arg.clone())
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Includes devices that are disabled, unplugged, or not present"),
+                )
+                .arg(
+                    Arg::new("flow")
+                        .long("flow")
+                        .value_name("FLOW")
+                        .possible_values(["render", "capture", "all"])
+                        .default_value("all")
+                        .help("Lists outputs (render), inputs (capture), or both"),
+                ),
         )
         .subcommand(
             Command::new("dump")
                 .about("Prints out the current configuration")
                 .arg(device_arg.clone())
+                .arg(capture_arg.clone())
                 .arg(output_format_arg.clone())
                 .arg(
                     Arg::new("output")
@@ -63,12 +103,50 @@ fn run() -> i32 {
                         .long("output")
                         .value_name("FILE")
                         .help("Saves the current settings to a file"),
+                )
+                .arg(
+                    Arg::new("volume-unit")
+                        .long("volume-unit")
+                        .value_name("UNIT")
+                        .possible_values(["scalar", "percent", "db"])
+                        .default_value("scalar")
+                        .help("Selects how the volume is written"),
+                )
+                .arg(
+                    Arg::new("typed")
+                        .long("typed")
+                        .help("Writes numbers with explicit types (e.g. { u32 = 5 })"),
+                )
+                .arg(context_arg.clone().help(
+                    "Dumps a SoundCore context by description or #id instead of the default context",
+                ))
+                .arg(
+                    Arg::new("all-contexts")
+                        .long("all-contexts")
+                        .conflicts_with("context")
+                        .help("Dumps every SoundCore context"),
+                )
+                .arg(
+                    Arg::new("annotate")
+                        .long("annotate")
+                        .help("Adds comments describing known parameters (toml and yaml only)"),
+                )
+                .arg(
+                    Arg::new("friendly")
+                        .long("friendly")
+                        .help("Writes known parameters by alias (e.g. output = \"speakers\")"),
+                )
+                .arg(
+                    Arg::new("ids")
+                        .long("ids")
+                        .help("Writes feature and parameter ids (e.g. \"#4097 EfxMasterControl\")"),
                 ),
         )
         .subcommand(
             Command::new("apply")
                 .about("Applies a saved configuration")
                 .arg(device_arg.clone())
+                .arg(capture_arg.clone())
                 .arg(input_format_arg)
                 .arg(
                     Arg::new("file")
@@ -88,6 +166,7 @@ fn run() -> i32 {
             Command::new("set")
                 .about("Sets specific parameters")
                 .arg(device_arg.clone())
+                .arg(capture_arg.clone())
                 .arg(
                     Arg::new("bool")
                         .short('b')
@@ -117,7 +196,25 @@ fn run() -> i32 {
                         .short('v')
                         .long("volume")
                         .value_name("VOLUME")
-                        .help("Sets the volume, in percent"),
+                        .allow_hyphen_values(true)
+                        .help("Sets or adjusts the volume, in percent or decibels (e.g. 60, +5, or -12.5dB)"),
+                )
+                .arg(
+                    Arg::new("muted")
+                        .long("muted")
+                        .value_name("true|false")
+                        .help("Mutes or unmutes the device"),
+                )
+                .arg(context_arg.clone().help(
+                    "Sets parameters in a SoundCore context by description or #id instead of the default context",
+                ))
+                .arg(
+                    Arg::new("set")
+                        .long("set")
+                        .help("Sets or adjusts a value (e.g. \"EfxMasterControl.Bass Level+=0.1\" or output=speakers)")
+                        .multiple_occurrences(true)
+                        .number_of_values(1)
+                        .value_name("[FEATURE.PARAMETER|ALIAS]=VALUE"),
                 )
                 .arg(
                     Arg::new("mute")
@@ -127,11 +224,140 @@ fn run() -> i32 {
                         .help("Temporarily mutes while changing parameters"),
                 ),
         )
+        .subcommand(
+            Command::new("set-default")
+                .about("Makes a device the Windows default device")
+                .arg(device_arg.clone().required(true))
+                .arg(capture_arg.clone())
+                .arg(
+                    Arg::new("role")
+                        .short('r')
+                        .long("role")
+                        .value_name("ROLE")
+                        .possible_values(["console", "multimedia", "communications"])
+                        .multiple_occurrences(true)
+                        .help("Selects a role to make the device the default for (all roles by default)"),
+                ),
+        )
+        .subcommand(
+            Command::new("describe")
+                .about("Describes the features and parameters of a device")
+                .arg(device_arg.clone())
+                .arg(capture_arg.clone())
+                .arg(output_format_arg.clone())
+                .arg(
+                    Arg::new("known")
+                        .long("known")
+                        .help("Only lists parameters described by the built-in catalog"),
+                ),
+        )
+        .subcommand(
+            Command::new("eq")
+                .about("Converts equalizer presets to and from the device's equalizer")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("import")
+                        .about("Maps a parametric or graphic equalizer preset onto the device's equalizer")
+                        .arg(device_arg.clone())
+                        .arg(capture_arg.clone())
+                        .arg(eq_feature_arg.clone())
+                        .arg(output_format_arg.clone())
+                        .arg(
+                            Arg::new("file")
+                                .short('i')
+                                .value_name("FILE")
+                                .help("Reads the preset from a file instead of stdin"),
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("FILE")
+                                .conflicts_with("apply")
+                                .help("Saves the profile to a file"),
+                        )
+                        .arg(
+                            Arg::new("apply")
+                                .long("apply")
+                                .help("Applies the preset instead of printing a profile"),
+                        )
+                        .arg(
+                            Arg::new("mute")
+                                .short('m')
+                                .value_name("true|false")
+                                .default_value("true")
+                                .help("Temporarily mutes while changing parameters"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Writes the device's equalizer as a preset")
+                        .arg(device_arg.clone())
+                        .arg(capture_arg.clone())
+                        .arg(eq_feature_arg)
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Saves the preset to a file"),
+                        )
+                        .arg(
+                            Arg::new("graphic")
+                                .long("graphic")
+                                .help("Writes a GraphicEQ curve instead of parametric filters"),
+                        ),
+                ),
+        )
         .subcommand(
             Command::new("watch")
                 .about("Watches for events")
                 .arg(device_arg.clone())
-                .arg(output_format_arg.clone()),
+                .arg(capture_arg.clone())
+                .arg(format_arg.clone().help(
+                    "Writes each event as a JSON line, a YAML document, or in another format instead of as text",
+                ))
+                .arg(
+                    Arg::new("feature")
+                        .long("feature")
+                        .value_name("PATTERN")
+                        .help("Only reports parameter changes of features matching a pattern"),
+                )
+                .arg(
+                    Arg::new("parameter")
+                        .long("parameter")
+                        .value_name("PATTERN")
+                        .help("Only reports changes of parameters matching a pattern"),
+                )
+                .arg(
+                    Arg::new("debounce")
+                        .long("debounce")
+                        .value_name("DURATION")
+                        .help("Combines changes of the same parameter or of the volume within a time, e.g. 200ms or 1s, into one event"),
+                )
+                .arg(
+                    Arg::new("poll")
+                        .long("poll")
+                        .value_name("DURATION")
+                        .help("Also reads the volume and the parameters matching --feature and --parameter at an interval, e.g. 1s, to report changes that the device does not notify"),
+                )
+                .arg(
+                    Arg::new("event-mask")
+                        .long("event-mask")
+                        .value_name("MASK")
+                        .help("Selects the SoundCore events to register for, e.g. 0xff (the default)"),
+                )
+                .arg(
+                    Arg::new("raw")
+                        .long("raw")
+                        .help("Also reports SoundCore events that could not be decoded, and the raw codes of every decoded one"),
+                )
+                .arg(
+                    Arg::new("volume-only")
+                        .long("volume-only")
+                        .conflicts_with_all(&["feature", "parameter"])
+                        .help("Only reports volume and mute changes"),
+                ),
         )
         .get_matches();
 
@@ -145,6 +371,13 @@ fn run() -> i32 {
         ("dump", sub_m) => dump(sub_m),
         ("apply", sub_m) => apply(sub_m),
         ("set", sub_m) => set(sub_m),
+        ("set-default", sub_m) => set_default(sub_m),
+        ("describe", sub_m) => describe(sub_m),
+        ("eq", sub_m) => match sub_m.subcommand().unwrap() {
+            ("import", sub_m) => eq_import(sub_m),
+            ("export", sub_m) => eq_export(sub_m),
+            _ => unreachable!(),
+        },
         ("watch", sub_m) => watch(sub_m),
         _ => unreachable!(),
     };
@@ -167,8 +400,22 @@ enum FormatError {
     TomlWrite(toml::ser::Error),
     Json(serde_json::Error),
     Yaml(serde_yaml::Error),
+    RonRead(ron::error::SpannedError),
+    RonWrite(ron::Error),
+    Json5(json5::Error),
+    Alias(AliasError),
     ValueError(&'static str),
-    ExpectedObject(String),
+    Volume(ParseVolumeError),
+    Param(ParseParamValueError),
+    UnknownType(String),
+    TypeMismatch(String, String),
+    UnknownRole(String),
+}
+
+impl From<AliasError> for FormatError {
+    fn from(error: AliasError) -> Self {
+        FormatError::Alias(error)
+    }
 }
 
 impl fmt::Display for FormatError {
@@ -178,8 +425,26 @@ impl fmt::Display for FormatError {
             FormatError::TomlWrite(error) => error.fmt(f),
             FormatError::Json(error) => error.fmt(f),
             FormatError::Yaml(error) => error.fmt(f),
+            FormatError::RonRead(error) => error.fmt(f),
+            FormatError::RonWrite(error) => error.fmt(f),
+            FormatError::Json5(error) => error.fmt(f),
+            FormatError::Alias(error) => error.fmt(f),
             FormatError::ValueError(error) => write!(f, "unsupported value of type {}", error),
-            FormatError::ExpectedObject(name) => write!(f, "expected {} to be an object", name),
+            FormatError::Volume(error) => error.fmt(f),
+            FormatError::Param(error) => error.fmt(f),
+            FormatError::UnknownType(name) => write!(
+                f,
+                "unknown value type {}; expected float, bool, u32, or i32",
+                name
+            ),
+            FormatError::TypeMismatch(name, value) => {
+                write!(f, "{} cannot be represented as {}", value, name)
+            }
+            FormatError::UnknownRole(name) => write!(
+                f,
+                "unknown role {}; expected console, multimedia, or communications",
+                name
+            ),
         }
     }
 }
@@ -191,371 +456,521 @@ impl Error for FormatError {
             FormatError::TomlWrite(error) => Some(error),
             FormatError::Json(error) => Some(error),
             FormatError::Yaml(error) => Some(error),
+            FormatError::RonRead(error) => Some(error),
+            FormatError::RonWrite(error) => Some(error),
+            FormatError::Json5(error) => Some(error),
+            FormatError::Alias(error) => Some(error),
             FormatError::ValueError(_) => None,
-            FormatError::ExpectedObject(_) => None,
+            FormatError::Volume(error) => Some(error),
+            FormatError::Param(error) => Some(error),
+            FormatError::UnknownType(_) => None,
+            FormatError::TypeMismatch(_, _) => None,
+            FormatError::UnknownRole(_) => None,
         }
     }
 }
 
-fn format_configuration(
-    value: &Configuration,
-    matches: &ArgMatches,
-) -> Result<String, FormatError> {
-    match matches.value_of("format").unwrap() {
-        "toml" => {
-            let value: SerdeConfiguration<BTreeMap<String, BTreeMap<String, Value>>> =
-                SerdeConfiguration {
-                    endpoint: value.endpoint.as_ref().map(From::from),
-                    creative: value.creative.as_ref().map(|creative| {
-                        creative
-                            .into_iter()
-                            .map(|(feature, params)| {
-                                (
-                                    feature.clone(),
-                                    params
-                                        .into_iter()
-                                        .map(|(key, value)| (key.clone(), Value::from_param(value)))
-                                        .collect(),
-                                )
-                            })
-                            .collect()
-                    }),
-                };
-            toml::to_string_pretty(&value).map_err(FormatError::TomlWrite)
-        }
-        "json" => {
-            let value: SerdeConfiguration<serde_json::Map<String, serde_json::Value>> =
-                SerdeConfiguration {
-                    endpoint: value.endpoint.as_ref().map(From::from),
-                    creative: value.creative.as_ref().map(|creative| {
-                        creative
-                            .into_iter()
-                            .map(|(feature, params)| {
-                                (
-                                    feature.to_string(),
-                                    serde_json::Value::Object(
-                                        params
-                                            .into_iter()
-                                            .map(|(key, value)| {
-                                                (
-                                                    key.to_string(),
-                                                    serde_json::Value::from_param(value),
-                                                )
-                                            })
-                                            .collect(),
-                                    ),
-                                )
-                            })
-                            .collect()
-                    }),
-                };
-            serde_json::to_string_pretty(&value).map_err(FormatError::Json)
-        }
-        "yaml" => {
-            let value: SerdeConfiguration<serde_yaml::Mapping> = SerdeConfiguration {
-                endpoint: value.endpoint.as_ref().map(From::from),
-                creative: value.creative.as_ref().map(|creative| {
-                    creative
-                        .into_iter()
-                        .map(|(feature, params)| {
-                            (
-                                serde_yaml::Value::String(feature.to_string()),
-                                serde_yaml::Value::Mapping(
-                                    params
-                                        .into_iter()
-                                        .map(|(key, value)| {
-                                            (
-                                                serde_yaml::Value::String(key.to_string()),
-                                                serde_yaml::Value::from_param(value),
-                                            )
-                                        })
-                                        .collect(),
-                                ),
-                            )
-                        })
-                        .collect()
-                }),
-            };
-            serde_yaml::to_string(&value).map_err(FormatError::Yaml)
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Toml,
+    Json,
+    Yaml,
+    Ron,
+    Json5,
+}
+
+const FORMAT_NAMES: [&str; 5] = ["toml", "json", "yaml", "ron", "json5"];
+
+impl Format {
+    fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "ron" => Some(Format::Ron),
+            "json5" => Some(Format::Json5),
+            _ => None,
         }
-        _ => unreachable!(),
     }
-}
 
-fn jobject_into_map(
-    value: serde_json::Value,
-) -> Result<serde_json::Map<String, serde_json::Value>, ()> {
-    match value {
-        serde_json::Value::Object(o) => Ok(o),
-        _ => Err(()),
+    fn from_path(path: &str) -> Option<Format> {
+        Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_name)
     }
-}
 
-fn ystring_into_string(value: serde_yaml::Value) -> Result<String, ()> {
-    match value {
-        serde_yaml::Value::String(s) => Ok(s),
-        _ => Err(()),
+    /// Guesses the format of a document from its contents.
+    fn sniff(text: &str) -> Format {
+        let first_line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(['#', '/']));
+        match first_line {
+            Some(line) if line.starts_with('{') => {
+                if serde_json::from_str::<serde::de::IgnoredAny>(text).is_ok() {
+                    Format::Json
+                } else {
+                    Format::Json5
+                }
+            }
+            Some(line) if line.starts_with('(') => Format::Ron,
+            Some(line) if line.starts_with('[') => Format::Toml,
+            Some(line) => match (line.find('='), line.find(':')) {
+                (Some(equals), Some(colon)) if equals < colon => Format::Toml,
+                (Some(_), None) => Format::Toml,
+                _ => Format::Yaml,
+            },
+            None => Format::Toml,
+        }
+    }
+
+    /// Selects the format from the `-f` argument, falling back to the file
+    /// extension and then to the contents of the document.
+    fn detect(matches: &ArgMatches, path: Option<&str>, text: Option<&str>) -> Format {
+        matches
+            .value_of("format")
+            .and_then(Format::from_name)
+            .or_else(|| path.and_then(Format::from_path))
+            .or_else(|| text.map(Format::sniff))
+            .unwrap_or(Format::Toml)
+    }
+
+    fn serialize<T: Serialize>(self, value: &T) -> Result<String, FormatError> {
+        match self {
+            // going through toml::Value writes plain values before tables, which TOML requires
+            Format::Toml => toml::Value::try_from(value)
+                .and_then(|value| toml::to_string_pretty(&value))
+                .map_err(FormatError::TomlWrite),
+            Format::Json => serde_json::to_string_pretty(value).map_err(FormatError::Json),
+            Format::Yaml => serde_yaml::to_string(value).map_err(FormatError::Yaml),
+            Format::Ron => ron::ser::to_string_pretty(
+                value,
+                ron::ser::PrettyConfig::new()
+                    .extensions(ron::extensions::Extensions::IMPLICIT_SOME),
+            )
+            .map_err(FormatError::RonWrite),
+            Format::Json5 => json5::to_string(value).map_err(FormatError::Json5),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(self, text: &str) -> Result<T, FormatError> {
+        match self {
+            Format::Toml => toml::from_str(text).map_err(FormatError::TomlRead),
+            Format::Json => serde_json::from_str(text).map_err(FormatError::Json),
+            Format::Yaml => serde_yaml::from_str(text).map_err(FormatError::Yaml),
+            Format::Ron => ron::Options::default()
+                .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+                .from_str(text)
+                .map_err(FormatError::RonRead),
+            Format::Json5 => json5::from_str(text).map_err(FormatError::Json5),
+        }
     }
 }
 
-fn yobject_into_map(value: serde_yaml::Value) -> Result<serde_yaml::Mapping, ()> {
-    match value {
-        serde_yaml::Value::Mapping(o) => Ok(o),
-        _ => Err(()),
+/// Controls how a configuration is written.
+#[derive(Default)]
+struct FormatOptions {
+    /// Writes the volume as a percentage instead of a scalar
+    volume_percent: bool,
+    /// Writes values with explicit types
+    typed: bool,
+    /// Writes aliases in the `friendly` section
+    friendly: bool,
+}
+
+fn format_configuration(
+    value: &Configuration,
+    format: Format,
+    options: &FormatOptions,
+) -> Result<String, FormatError> {
+    format.serialize(&serde_configuration(value, options))
+}
+
+fn serde_configuration(value: &Configuration, options: &FormatOptions) -> SerdeConfiguration {
+    let FormatOptions {
+        volume_percent,
+        typed,
+        friendly,
+    } = *options;
+    let catalog = Catalog::builtin();
+    let mut creative = value.creative.clone();
+    let friendly = match creative {
+        Some(ref mut creative) if friendly => Some(
+            extract_aliases(&catalog, creative)
+                .into_iter()
+                .map(|(alias, value)| {
+                    let value = match value {
+                        Setting::Absolute(ref v) => match alias.label(v) {
+                            Some(label) => SerdeParamValue::Text(label.to_owned()),
+                            None => SerdeParamValue::new(&value, typed),
+                        },
+                        Setting::Relative(_) => SerdeParamValue::new(&value, typed),
+                    };
+                    (alias.name.to_owned(), value)
+                })
+                .collect(),
+        ),
+        _ => None,
+    };
+    SerdeConfiguration {
+        friendly,
+        endpoint: value
+            .endpoint
+            .as_ref()
+            .map(|endpoint| SerdeEndpointConfiguration::new(endpoint, volume_percent)),
+        creative: creative.map(|creative| format_creative(creative, typed)),
+        contexts: value.contexts.as_ref().map(|contexts| {
+            contexts
+                .iter()
+                .map(|(context, creative)| {
+                    (context.clone(), format_creative(creative.clone(), typed))
+                })
+                .collect()
+        }),
+        endpoints: value.endpoints.clone(),
     }
 }
 
-fn transpose<T, E>(value: Option<Result<T, E>>) -> Result<Option<T>, E> {
-    match value {
-        Some(Ok(value)) => Ok(Some(value)),
-        Some(Err(error)) => Err(error),
-        None => Ok(None),
-    }
-}
-
-fn unformat_configuration(value: &str, matches: &ArgMatches) -> Result<Configuration, FormatError> {
-    Ok(match matches.value_of("format").unwrap() {
-        "toml" => {
-            let value: SerdeConfiguration<BTreeMap<String, BTreeMap<String, Value>>> =
-                toml::from_str(value).map_err(FormatError::TomlRead)?;
-            Configuration {
-                endpoint: value.endpoint.map(Into::into),
-                creative: transpose(value.creative.map(|creative| {
-                    Ok({
-                        creative
-                            .into_iter()
-                            .map(|(feature, params)| {
-                                Ok({
-                                    (
-                                        feature,
-                                        params
-                                            .into_iter()
-                                            .map(|(key, value)| {
-                                                Ok((
-                                                    key,
-                                                    Value::try_into_param(value)
-                                                        .map_err(FormatError::ValueError)?,
-                                                ))
-                                            })
-                                            .collect::<Result<_, _>>()?,
-                                    )
-                                })
-                            })
-                            .collect::<Result<_, _>>()?
-                    })
-                }))?,
-            }
-        }
-        "json" => {
-            let value: SerdeConfiguration<serde_json::Map<String, serde_json::Value>> =
-                serde_json::from_str(value).map_err(FormatError::Json)?;
-            Configuration {
-                endpoint: value.endpoint.map(Into::into),
-                creative: transpose(value.creative.map(|creative| {
-                    Ok({
-                        creative
-                            .into_iter()
-                            .map(|(feature, params)| {
-                                Ok({
-                                    let params = match jobject_into_map(params) {
-                                        Ok(params) => params,
-                                        Err(_) => return Err(FormatError::ExpectedObject(feature)),
-                                    };
-                                    (
-                                        feature,
-                                        params
-                                            .into_iter()
-                                            .map(|(key, value)| {
-                                                Ok((
-                                                    key,
-                                                    serde_json::Value::try_into_param(value)
-                                                        .map_err(FormatError::ValueError)?,
-                                                ))
-                                            })
-                                            .collect::<Result<_, _>>()?,
-                                    )
-                                })
-                            })
-                            .collect::<Result<_, _>>()?
-                    })
-                }))?,
-            }
+fn format_creative(
+    creative: IndexMap<String, IndexMap<String, Setting<SoundCoreParamValue>>>,
+    typed: bool,
+) -> SerdeCreative {
+    creative
+        .into_iter()
+        .map(|(feature, params)| {
+            (
+                feature,
+                params
+                    .into_iter()
+                    .map(|(key, value)| (key, SerdeParamValue::new(&value, typed)))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+fn unformat_configuration(value: &str, format: Format) -> Result<Configuration, FormatError> {
+    let value: SerdeConfiguration = format.deserialize(value)?;
+    let catalog = Catalog::builtin();
+    let mut creative = value
+        .creative
+        .map(|creative| unformat_creative(&catalog, creative))
+        .transpose()?;
+    if let Some(friendly) = value.friendly {
+        let creative = creative.get_or_insert_with(IndexMap::new);
+        for (name, value) in friendly {
+            let alias = Alias::find(&catalog, &name)?;
+            insert_alias(
+                &catalog,
+                creative,
+                alias.name,
+                value.into_setting(Some(&alias))?,
+            )?;
         }
-        "yaml" => {
-            let value: SerdeConfiguration<serde_yaml::Mapping> =
-                serde_yaml::from_str(value).map_err(FormatError::Yaml)?;
-            Configuration {
-                endpoint: value.endpoint.map(Into::into),
-                creative: transpose(value.creative.map(|creative| {
-                    Ok({
-                        creative
-                            .into_iter()
-                            .map(|(feature, params)| {
-                                Ok({
-                                    let feature = ystring_into_string(feature)
-                                        .expect("yaml property name was not a string");
-                                    let params = match yobject_into_map(params) {
-                                        Ok(params) => params,
-                                        Err(_) => return Err(FormatError::ExpectedObject(feature)),
-                                    };
-                                    (
-                                        feature,
-                                        params
-                                            .into_iter()
-                                            .map(|(key, value)| {
-                                                Ok((
-                                                    ystring_into_string(key).expect(
-                                                        "yaml property name was not a string",
-                                                    ),
-                                                    serde_yaml::Value::try_into_param(value)
-                                                        .map_err(FormatError::ValueError)?,
-                                                ))
-                                            })
-                                            .collect::<Result<_, _>>()?,
-                                    )
-                                })
-                            })
-                            .collect::<Result<_, _>>()?
+    }
+    Ok(Configuration {
+        endpoint: value.endpoint.map(TryInto::try_into).transpose()?,
+        creative,
+        contexts: value
+            .contexts
+            .map(|contexts| {
+                contexts
+                    .into_iter()
+                    .map(|(context, creative)| {
+                        Ok((context, unformat_creative(&catalog, creative)?))
                     })
-                }))?,
-            }
-        }
-        _ => unreachable!(),
+                    .collect::<Result<_, FormatError>>()
+            })
+            .transpose()?,
+        endpoints: value.endpoints,
     })
 }
 
+fn unformat_creative(
+    catalog: &Catalog,
+    creative: SerdeCreative,
+) -> Result<IndexMap<String, IndexMap<String, Setting<SoundCoreParamValue>>>, FormatError> {
+    creative
+        .into_iter()
+        .map(|(feature, params)| {
+            let params = params
+                .into_iter()
+                .map(|(key, value)| {
+                    let alias = Alias::for_parameter(catalog, &feature, &key);
+                    Ok((key, value.into_setting(alias.as_ref())?))
+                })
+                .collect::<Result<_, FormatError>>()?;
+            Ok((feature, params))
+        })
+        .collect()
+}
+
 #[derive(Deserialize, Serialize)]
-pub struct SerdeEndpointConfiguration {
-    pub volume: Option<f32>,
+#[serde(untagged)]
+enum SerdeVolume {
+    Scalar(f32),
+    Text(String),
 }
 
-impl From<&EndpointConfiguration> for SerdeEndpointConfiguration {
-    fn from(value: &EndpointConfiguration) -> Self {
-        Self {
-            volume: value.volume,
+impl SerdeVolume {
+    fn new(value: Setting<Volume>, percent: bool) -> Self {
+        match value {
+            Setting::Absolute(Volume::Scalar(v)) if !percent => SerdeVolume::Scalar(v),
+            _ => SerdeVolume::Text(value.to_string()),
         }
     }
 }
 
-impl From<SerdeEndpointConfiguration> for EndpointConfiguration {
-    fn from(value: SerdeEndpointConfiguration) -> Self {
-        Self {
-            volume: value.volume,
+impl TryFrom<SerdeVolume> for Setting<Volume> {
+    type Error = ParseVolumeError;
+
+    fn try_from(value: SerdeVolume) -> Result<Self, Self::Error> {
+        match value {
+            SerdeVolume::Scalar(v) => Ok(Setting::Absolute(Volume::Scalar(v))),
+            SerdeVolume::Text(text) => Setting::from_str(&text),
         }
     }
 }
 
 #[derive(Deserialize, Serialize)]
-struct SerdeConfiguration<TOuter> {
-    endpoint: Option<SerdeEndpointConfiguration>,
-    creative: Option<TOuter>,
+struct SerdeEndpointConfiguration {
+    volume: Option<SerdeVolume>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mute: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<SerdeDefault>,
 }
 
-trait ParamConvert {
-    fn try_into_param(value: Self) -> Result<SoundCoreParamValue, &'static str>;
-    fn from_param(value: &SoundCoreParamValue) -> Self;
+/// The roles to make a device the default for: `true` for all roles, or
+/// one or more role names.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum SerdeDefault {
+    All(bool),
+    Role(String),
+    Roles(Vec<String>),
 }
 
-impl ParamConvert for toml::Value {
-    fn try_into_param(value: Self) -> Result<SoundCoreParamValue, &'static str> {
+impl TryFrom<SerdeDefault> for Vec<DeviceRole> {
+    type Error = FormatError;
+
+    fn try_from(value: SerdeDefault) -> Result<Self, Self::Error> {
+        let parse =
+            |name: String| DeviceRole::from_name(&name).ok_or(FormatError::UnknownRole(name));
         match value {
-            Value::Float(f) => Ok(SoundCoreParamValue::Float(f as f32)),
-            Value::Boolean(b) => Ok(SoundCoreParamValue::Bool(b)),
-            Value::Integer(i)
-                if i < i64::from(i32::min_value()) || i64::from(u32::max_value()) < i =>
-            {
-                Err("Large integer")
-            }
-            Value::Integer(i) if i64::from(i32::max_value()) <= i => {
-                Ok(SoundCoreParamValue::U32(i as u32))
-            }
-            Value::Integer(i) => Ok(SoundCoreParamValue::I32(i as i32)),
-            Value::Array(_) => Err("Array"),
-            Value::Datetime(_) => Err("Datetime"),
-            Value::Table(_) => Err("Table"),
-            Value::String(_) => Err("String"),
+            SerdeDefault::All(true) => Ok(DeviceRole::ALL.to_vec()),
+            SerdeDefault::All(false) => Ok(Vec::new()),
+            SerdeDefault::Role(name) => Ok(vec![parse(name)?]),
+            SerdeDefault::Roles(names) => names.into_iter().map(parse).collect(),
         }
     }
-    fn from_param(value: &SoundCoreParamValue) -> Self {
-        match value {
-            SoundCoreParamValue::Float(f) => Value::Float((*f).into()),
-            SoundCoreParamValue::Bool(b) => Value::Boolean(*b),
-            SoundCoreParamValue::U32(i) => Value::Integer(i64::from(*i)),
-            SoundCoreParamValue::I32(i) => Value::Integer(i64::from(*i)),
-            _ => Value::String("<unsupported>".to_string()),
+}
+
+impl SerdeEndpointConfiguration {
+    fn new(value: &EndpointConfiguration, volume_percent: bool) -> Self {
+        Self {
+            volume: value
+                .volume
+                .map(|volume| SerdeVolume::new(volume, volume_percent)),
+            mute: value.mute,
+            default: None,
         }
     }
 }
 
-impl ParamConvert for serde_json::Value {
-    fn try_into_param(value: Self) -> Result<SoundCoreParamValue, &'static str> {
+impl TryFrom<SerdeEndpointConfiguration> for EndpointConfiguration {
+    type Error = FormatError;
+
+    fn try_from(value: SerdeEndpointConfiguration) -> Result<Self, Self::Error> {
+        Ok(Self {
+            volume: value
+                .volume
+                .map(TryInto::try_into)
+                .transpose()
+                .map_err(FormatError::Volume)?,
+            mute: value.mute,
+            default: value
+                .default
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct SerdeConfiguration {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    friendly: Option<IndexMap<String, SerdeParamValue>>,
+    endpoint: Option<SerdeEndpointConfiguration>,
+    creative: Option<SerdeCreative>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    contexts: Option<IndexMap<String, SerdeCreative>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    endpoints: Option<IndexMap<String, bool>>,
+}
+
+/// The parameters of each feature of a context.
+type SerdeCreative = IndexMap<String, IndexMap<String, SerdeParamValue>>;
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum SerdeParamValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    Typed(BTreeMap<String, SerdeParamValue>),
+}
+
+impl SerdeParamValue {
+    fn new(value: &Setting<SoundCoreParamValue>, typed: bool) -> Self {
+        let plain = match value {
+            Setting::Absolute(SoundCoreParamValue::Float(f)) => SerdeParamValue::Float((*f).into()),
+            Setting::Absolute(SoundCoreParamValue::Bool(b)) => SerdeParamValue::Bool(*b),
+            Setting::Absolute(SoundCoreParamValue::U32(i)) => SerdeParamValue::Integer((*i).into()),
+            Setting::Absolute(SoundCoreParamValue::I32(i)) => SerdeParamValue::Integer((*i).into()),
+            // data is written as a base64 string, which is never ambiguous
+            Setting::Absolute(SoundCoreParamValue::Bytes(_)) | Setting::Relative(_) => {
+                SerdeParamValue::Text(value.to_string())
+            }
+            _ => SerdeParamValue::Text("<unsupported>".to_string()),
+        };
         match value {
-            serde_json::Value::Number(n) => match n.as_i64() {
-                Some(n) if n < i64::from(i32::min_value()) => Err("Large integer"),
-                Some(n) if n <= i64::from(i32::max_value()) => {
-                    Ok(SoundCoreParamValue::I32(n as i32))
+            Setting::Absolute(v) if typed => match param_type_name(v) {
+                Some(name) => {
+                    SerdeParamValue::Typed(std::iter::once((name.to_owned(), plain)).collect())
                 }
-                Some(n) if n <= i64::from(u32::max_value()) => {
-                    Ok(SoundCoreParamValue::U32(n as u32))
-                }
-                Some(_) => Err("Large integer"),
-                None => Ok(SoundCoreParamValue::Float(n.as_f64().unwrap() as f32)),
+                None => plain,
             },
-            serde_json::Value::Bool(b) => Ok(SoundCoreParamValue::Bool(b)),
-            serde_json::Value::Array(_) => Err("Array"),
-            serde_json::Value::Object(_) => Err("Object"),
-            serde_json::Value::String(_) => Err("String"),
-            serde_json::Value::Null => Err("Null"),
+            _ => plain,
         }
     }
-    fn from_param(value: &SoundCoreParamValue) -> Self {
-        match value {
-            SoundCoreParamValue::Float(f) => serde_json::Value::from(*f),
-            SoundCoreParamValue::Bool(b) => serde_json::Value::from(*b),
-            SoundCoreParamValue::U32(i) => serde_json::Value::from(*i),
-            SoundCoreParamValue::I32(i) => serde_json::Value::from(*i),
-            _ => serde_json::Value::String("<unsupported>".to_string()),
+}
+
+impl fmt::Display for SerdeParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerdeParamValue::Bool(b) => b.fmt(f),
+            SerdeParamValue::Integer(i) => i.fmt(f),
+            SerdeParamValue::Float(n) => n.fmt(f),
+            SerdeParamValue::Text(text) => f.write_str(text),
+            SerdeParamValue::Typed(values) => match values.iter().next() {
+                Some((name, value)) => write!(f, "{} {}", value, name),
+                None => Ok(()),
+            },
         }
     }
 }
 
-impl ParamConvert for serde_yaml::Value {
-    fn try_into_param(value: Self) -> Result<SoundCoreParamValue, &'static str> {
-        match value {
-            serde_yaml::Value::Number(n) => match n.as_i64() {
-                Some(n) if n < i64::from(i32::min_value()) => Err("Large integer"),
-                Some(n) if n <= i64::from(i32::max_value()) => {
-                    Ok(SoundCoreParamValue::I32(n as i32))
+impl TryFrom<SerdeParamValue> for Setting<SoundCoreParamValue> {
+    type Error = FormatError;
+
+    fn try_from(value: SerdeParamValue) -> Result<Self, Self::Error> {
+        value.into_setting(None)
+    }
+}
+
+impl SerdeParamValue {
+    /// Converts the value, accepting the labels of `alias` in place of numbers.
+    fn into_setting(
+        self,
+        alias: Option<&Alias>,
+    ) -> Result<Setting<SoundCoreParamValue>, FormatError> {
+        let value = match self {
+            SerdeParamValue::Bool(b) => SoundCoreParamValue::Bool(b),
+            SerdeParamValue::Integer(i) if i < i64::from(i32::MIN) || i64::from(u32::MAX) < i => {
+                return Err(FormatError::ValueError("Large integer"))
+            }
+            SerdeParamValue::Integer(i) if i <= i64::from(i32::MAX) => {
+                SoundCoreParamValue::I32(i as i32)
+            }
+            SerdeParamValue::Integer(i) => SoundCoreParamValue::U32(i as u32),
+            SerdeParamValue::Float(f) => SoundCoreParamValue::Float(f as f32),
+            SerdeParamValue::Text(s) => {
+                return match alias {
+                    Some(alias) => alias.parse_setting(&s).map_err(FormatError::Alias),
+                    None => Setting::from_str(&s).map_err(FormatError::Param),
                 }
-                Some(n) if n <= i64::from(u32::max_value()) => {
-                    Ok(SoundCoreParamValue::U32(n as u32))
+            }
+            SerdeParamValue::Typed(t) => {
+                return match single_entry(t) {
+                    Some((name, value)) => retype_param(&name, value.try_into()?),
+                    None => Err(FormatError::ValueError("Table")),
                 }
-                Some(_) => Err("Large integer"),
-                None => Ok(SoundCoreParamValue::Float(n.as_f64().unwrap() as f32)),
-            },
-            serde_yaml::Value::Bool(b) => Ok(SoundCoreParamValue::Bool(b)),
-            serde_yaml::Value::Sequence(_) => Err("Sequence"),
-            serde_yaml::Value::Mapping(_) => Err("Mapping"),
-            serde_yaml::Value::String(_) => Err("String"),
-            serde_yaml::Value::Null => Err("Null"),
-        }
+            }
+        };
+        Ok(Setting::Absolute(value))
     }
-    fn from_param(value: &SoundCoreParamValue) -> Self {
-        match value {
-            SoundCoreParamValue::Float(f) => serde_yaml::Value::from(*f),
-            SoundCoreParamValue::Bool(b) => serde_yaml::Value::from(*b),
-            SoundCoreParamValue::U32(i) => serde_yaml::Value::from(*i),
-            SoundCoreParamValue::I32(i) => serde_yaml::Value::from(*i),
-            _ => serde_yaml::Value::String("<unsupported>".to_string()),
+}
+
+fn param_type_name(value: &SoundCoreParamValue) -> Option<&'static str> {
+    match value {
+        SoundCoreParamValue::Float(_) => Some("float"),
+        SoundCoreParamValue::U32(_) => Some("u32"),
+        SoundCoreParamValue::I32(_) => Some("i32"),
+        // booleans are never ambiguous
+        _ => None,
+    }
+}
+
+fn retype_param(
+    name: &str,
+    value: Setting<SoundCoreParamValue>,
+) -> Result<Setting<SoundCoreParamValue>, FormatError> {
+    let absolute = match value {
+        Setting::Absolute(value) => value,
+        // the type of an adjustment does not matter because it is converted to the parameter type
+        Setting::Relative(_) if ["float", "bool", "u32", "i32"].contains(&name) => {
+            return Ok(value)
         }
+        Setting::Relative(_) => return Err(FormatError::UnknownType(name.to_owned())),
+    };
+    let converted = match (name, &absolute) {
+        ("float", &SoundCoreParamValue::Float(f)) => SoundCoreParamValue::Float(f),
+        ("float", &SoundCoreParamValue::U32(i)) => SoundCoreParamValue::Float(i as f32),
+        ("float", &SoundCoreParamValue::I32(i)) => SoundCoreParamValue::Float(i as f32),
+        ("bool", &SoundCoreParamValue::Bool(b)) => SoundCoreParamValue::Bool(b),
+        ("u32", &SoundCoreParamValue::U32(i)) => SoundCoreParamValue::U32(i),
+        ("u32", &SoundCoreParamValue::I32(i)) if 0 <= i => SoundCoreParamValue::U32(i as u32),
+        ("i32", &SoundCoreParamValue::I32(i)) => SoundCoreParamValue::I32(i),
+        ("i32", &SoundCoreParamValue::U32(i)) if i <= i32::MAX as u32 => {
+            SoundCoreParamValue::I32(i as i32)
+        }
+        ("float" | "bool" | "u32" | "i32", _) => {
+            return Err(FormatError::TypeMismatch(
+                name.to_owned(),
+                absolute.to_string(),
+            ))
+        }
+        _ => return Err(FormatError::UnknownType(name.to_owned())),
+    };
+    Ok(Setting::Absolute(converted))
+}
+
+fn single_entry<I: IntoIterator>(iter: I) -> Option<I::Item> {
+    let mut iter = iter.into_iter();
+    match (iter.next(), iter.next()) {
+        (Some(entry), None) => Some(entry),
+        _ => None,
     }
 }
 
 #[derive(Serialize)]
 struct SerializableDeviceInfo {
     id: String,
-    interface: String,
-    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interface: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    flow: &'static str,
+    state: &'static str,
+    soundcore: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clsid_source: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    default_for: Vec<&'static str>,
 }
 
 impl From<DeviceInfo> for SerializableDeviceInfo {
@@ -564,29 +979,230 @@ impl From<DeviceInfo> for SerializableDeviceInfo {
             id: value.id,
             interface: value.interface,
             description: value.description,
+            flow: value.flow.name(),
+            state: value.state.name(),
+            soundcore: value.soundcore.is_some(),
+            clsid_source: value.soundcore.map(ClsidSource::name),
+            default_for: value
+                .default_for
+                .into_iter()
+                .map(DeviceRole::name)
+                .collect(),
         }
     }
 }
 
+#[derive(Serialize)]
+struct SerializableDescription {
+    device: SerializableDeviceInfo,
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    contexts: Vec<SerializableContext>,
+    features: Vec<SerializableFeature>,
+}
+
+#[derive(Serialize)]
+struct SerializableContext {
+    id: u32,
+    description: String,
+}
+
+#[derive(Serialize)]
+struct SerializableFeature {
+    id: u32,
+    description: String,
+    version: String,
+    parameters: Vec<SerializableParameter>,
+}
+
+#[derive(Serialize)]
+struct SerializableParameter {
+    id: u32,
+    description: String,
+    kind: &'static str,
+    read_only: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    step: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    about: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<String>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    labels: IndexMap<String, CatalogValue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    requires: Vec<String>,
+}
+
+impl From<ParameterDescription> for SerializableParameter {
+    fn from(value: ParameterDescription) -> SerializableParameter {
+        let text = |value: SoundCoreParamValue| match value {
+            SoundCoreParamValue::None => None,
+            value => Some(value.to_string()),
+        };
+        let info = value.info;
+        SerializableParameter {
+            id: value.id,
+            description: value.description,
+            kind: match value.kind {
+                0 => "float",
+                1 => "bool",
+                2 => "u32",
+                3 => "i32",
+                5 => "bytes",
+                _ => "unknown",
+            },
+            read_only: value.read_only,
+            size: value.size,
+            min: text(value.min_value),
+            max: text(value.max_value),
+            step: text(value.step_size),
+            about: info.as_ref().and_then(|info| info.description.clone()),
+            unit: info.as_ref().and_then(|info| info.unit.clone()),
+            labels: info
+                .as_ref()
+                .map(|info| info.labels.clone())
+                .unwrap_or_default(),
+            requires: info
+                .map(|info| {
+                    info.requires
+                        .iter()
+                        .map(|requirement| match requirement.reason {
+                            Some(ref reason) => format!("{} ({})", requirement, reason),
+                            None => requirement.to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Loads the device aliases from the default alias file.
+fn load_device_aliases() -> Result<DeviceAliases, Box<dyn Error>> {
+    match DeviceAliases::default_path() {
+        Some(path) => DeviceAliases::load(&path),
+        None => Ok(DeviceAliases::default()),
+    }
+}
+
+/// Resolves the device argument, which may be an alias or a selector, to a
+/// device id.
+///
+/// With `--capture`, selectors and the default device refer to inputs.
+fn device_id(matches: &ArgMatches) -> Result<Option<HSTRING>, Box<dyn Error>> {
+    let capture = matches.is_present("capture");
+    let flow = if capture {
+        DataFlow::Capture
+    } else {
+        DataFlow::Render
+    };
+    let text = match matches.value_of_lossy("device") {
+        Some(text) => text,
+        // the library uses the default output when no device is given
+        None if capture => "role:console".into(),
+        None => return Ok(None),
+    };
+    let id = load_device_aliases()?.selector(&text)?.resolve(flow)?;
+    debug!(selector = %text, id = %id, "Selected device");
+    Ok(Some(HSTRING::from(id.as_str())))
+}
+
+fn describe(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let description = sbz_switch::describe(device_id(matches)?.as_ref())?;
+    let known_only = matches.is_present("known");
+    let description = SerializableDescription {
+        device: description.device.into(),
+        model: description.model.map(|model| model.name),
+        contexts: description
+            .contexts
+            .into_iter()
+            .map(|context| SerializableContext {
+                id: context.id,
+                description: context.description,
+            })
+            .collect(),
+        features: description
+            .features
+            .into_iter()
+            .map(|feature| SerializableFeature {
+                id: feature.id,
+                description: feature.description,
+                version: feature.version,
+                parameters: feature
+                    .parameters
+                    .into_iter()
+                    .filter(|parameter| !known_only || parameter.info.is_some())
+                    .map(SerializableParameter::from)
+                    .collect(),
+            })
+            .filter(|feature| !known_only || !feature.parameters.is_empty())
+            .collect(),
+    };
+    let text = Format::detect(matches, None, None).serialize(&description)?;
+    print!("{}", text);
+    Ok(())
+}
+
 fn list_devices(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let devices: Vec<_> = sbz_switch::list_devices()?
+    let options = ListOptions {
+        flow: match matches.value_of("flow") {
+            Some("render") => Some(DataFlow::Render),
+            Some("capture") => Some(DataFlow::Capture),
+            _ => None,
+        },
+        include_inactive: matches.is_present("all"),
+    };
+    let devices: Vec<_> = sbz_switch::list_devices_with_options(&options)?
         .into_iter()
         .map(SerializableDeviceInfo::from)
         .collect();
-    let text = match matches.value_of("format").unwrap() {
-        "toml" => toml::to_string_pretty(&devices).map_err(FormatError::TomlWrite)?,
-        "json" => serde_json::to_string_pretty(&devices).map_err(FormatError::Json)?,
-        "yaml" => serde_yaml::to_string(&devices).map_err(FormatError::Yaml)?,
-        _ => unreachable!(),
-    };
+    let text = Format::detect(matches, None, None).serialize(&devices)?;
     print!("{}", text);
     Ok(())
 }
 
 fn dump(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let table = sbz_switch::dump(matches.value_of_os("device").map(HSTRING::from).as_ref())?;
-    let text = format_configuration(&table, matches)?;
+    let options = DumpOptions {
+        volume_in_decibels: matches.value_of("volume-unit") == Some("db"),
+        include_ids: matches.is_present("ids"),
+        context: matches.value_of("context").map(str::to_owned),
+        all_contexts: matches.is_present("all-contexts"),
+    };
+    let device = device_id(matches)?;
+    let table = sbz_switch::dump_with_options(device.as_ref(), &options)?;
     let output = matches.value_of("output");
+    let format = Format::detect(matches, output, None);
+    let format_options = FormatOptions {
+        volume_percent: matches.value_of("volume-unit") == Some("percent"),
+        typed: matches.is_present("typed"),
+        friendly: matches.is_present("friendly"),
+    };
+    let text = match format {
+        Format::Toml | Format::Yaml if matches.is_present("annotate") => {
+            let device = sbz_switch::device_info(device.as_ref())?;
+            let catalog = Catalog::builtin();
+            let model = device
+                .interface
+                .as_deref()
+                .and_then(|interface| catalog.model_for_interface(interface));
+            let annotate = |feature: &str, parameter: &str| {
+                annotation(&catalog, model, &table, feature, parameter)
+            };
+            format_annotated(&table, format, &format_options, annotate)?
+        }
+        _ => {
+            if matches.is_present("annotate") {
+                warn!("Annotations can only be written in toml or yaml");
+            }
+            format_configuration(&table, format, &format_options)?
+        }
+    };
     match output {
         Some(name) => write!(File::create(name)?, "{}", text)?,
         _ => print!("{}", text),
@@ -594,22 +1210,281 @@ fn dump(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Describes a parameter value using the catalog, for a comment in a dump.
+fn annotation(
+    catalog: &Catalog,
+    model: Option<&Model>,
+    configuration: &Configuration,
+    feature: &str,
+    parameter: &str,
+) -> Option<String> {
+    let info = catalog.parameter(
+        model,
+        ItemKey::parse(feature).description()?,
+        ItemKey::parse(parameter).description()?,
+    )?;
+    let value = configuration
+        .creative
+        .as_ref()
+        .and_then(|creative| creative.get(feature))
+        .and_then(|parameters| parameters.get(parameter));
+    let label = match value {
+        Some(Setting::Absolute(value)) => info.label(value),
+        _ => None,
+    };
+    let parts: Vec<_> = label
+        .map(str::to_owned)
+        .into_iter()
+        .chain(info.description.clone())
+        .chain(info.unit.as_ref().map(|unit| format!("in {}", unit)))
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("; "))
+    }
+}
+
+/// Writes a configuration in TOML or YAML with a comment after each
+/// parameter of the `creative` section that `annotate` describes.
+///
+/// The serializers cannot write comments, so the `creative` section is
+/// written from the configuration here, and the other sections are
+/// serialized as usual.
+fn format_annotated<F>(
+    value: &Configuration,
+    format: Format,
+    options: &FormatOptions,
+    annotate: F,
+) -> Result<String, FormatError>
+where
+    F: Fn(&str, &str) -> Option<String>,
+{
+    let mut value = serde_configuration(value, options);
+    let creative = value.creative.take().unwrap_or_default();
+    match format {
+        Format::Toml => {
+            // TOML allows the tables of the `creative` section after the others
+            let mut text = format.serialize(&value)?;
+            for (feature, parameters) in &creative {
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&format!("[creative.{}]\n", toml_key(feature)));
+                for (parameter, setting) in parameters {
+                    let line = format!("{} = {}", toml_key(parameter), toml_value(setting)?);
+                    push_annotated(&mut text, &line, annotate(feature, parameter));
+                }
+            }
+            Ok(text)
+        }
+        Format::Yaml => {
+            // keep the `creative` section in its usual place
+            let mut before = serde_yaml::Mapping::new();
+            let mut after = serde_yaml::Mapping::new();
+            let mut found = false;
+            let document = serde_yaml::to_value(&value).map_err(FormatError::Yaml)?;
+            if let serde_yaml::Value::Mapping(mapping) = document {
+                for (key, section) in mapping {
+                    if key.as_str() == Some("creative") {
+                        found = true;
+                    } else if found {
+                        after.insert(key, section);
+                    } else {
+                        before.insert(key, section);
+                    }
+                }
+            }
+            let mut text = if before.is_empty() {
+                "---\n".to_owned()
+            } else {
+                format.serialize(&before)?
+            };
+            if !creative.is_empty() {
+                text.push_str("creative:\n");
+            }
+            for (feature, parameters) in &creative {
+                text.push_str(&format!("  {}:\n", yaml_scalar(feature)?));
+                for (parameter, setting) in parameters {
+                    let line = format!("    {}: {}", yaml_scalar(parameter)?, yaml_value(setting)?);
+                    push_annotated(&mut text, &line, annotate(feature, parameter));
+                }
+            }
+            if !after.is_empty() {
+                let rest = format.serialize(&after)?;
+                text.push_str(rest.strip_prefix("---\n").unwrap_or(&rest));
+            }
+            Ok(text)
+        }
+        _ => format.serialize(&value),
+    }
+}
+
+/// Adds a line to an annotated document, with its comment.
+fn push_annotated(text: &mut String, line: &str, comment: Option<String>) {
+    text.push_str(line);
+    if let Some(comment) = comment {
+        text.push_str(" # ");
+        text.push_str(&comment);
+    }
+    text.push('\n');
+}
+
+/// Writes a TOML key, quoting it if needed.
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_owned()
+    } else {
+        toml::Value::String(key.to_owned()).to_string()
+    }
+}
+
+/// Writes a parameter value as an inline TOML value.
+fn toml_value(value: &SerdeParamValue) -> Result<String, FormatError> {
+    match value {
+        SerdeParamValue::Typed(values) => {
+            let entries = values
+                .iter()
+                .map(|(key, value)| Ok(format!("{} = {}", toml_key(key), toml_value(value)?)))
+                .collect::<Result<Vec<_>, FormatError>>()?;
+            Ok(format!("{{ {} }}", entries.join(", ")))
+        }
+        _ => Ok(toml::Value::try_from(value)
+            .map_err(FormatError::TomlWrite)?
+            .to_string()),
+    }
+}
+
+/// Writes a YAML scalar, quoting it if needed.
+fn yaml_scalar<T: Serialize + ?Sized>(value: &T) -> Result<String, FormatError> {
+    let text = serde_yaml::to_string(value).map_err(FormatError::Yaml)?;
+    let text = text.strip_prefix("---").unwrap_or(&text).trim();
+    Ok(text.to_owned())
+}
+
+/// Writes a parameter value as a YAML value on a single line.
+fn yaml_value(value: &SerdeParamValue) -> Result<String, FormatError> {
+    match value {
+        SerdeParamValue::Typed(values) => {
+            let entries = values
+                .iter()
+                .map(|(key, value)| Ok(format!("{}: {}", yaml_scalar(key)?, yaml_value(value)?)))
+                .collect::<Result<Vec<_>, FormatError>>()?;
+            Ok(format!("{{{}}}", entries.join(", ")))
+        }
+        _ => yaml_scalar(value),
+    }
+}
+
 fn apply(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let mut text = String::new();
-    match matches.value_of("file") {
+    let file = matches.value_of("file");
+    match file {
         Some(name) => BufReader::new(File::open(name)?).read_to_string(&mut text)?,
         None => io::stdin().read_to_string(&mut text)?,
     };
 
-    let configuration: Configuration = unformat_configuration(&text, matches)?;
+    let format = Format::detect(matches, file, Some(&text));
+    let mut configuration: Configuration = unformat_configuration(&text, format)?;
     mem::drop(text);
 
+    if let Some(endpoints) = configuration.endpoints.take() {
+        let aliases = load_device_aliases()?;
+        configuration.endpoints = Some(
+            endpoints
+                .into_iter()
+                .map(|(device, enabled)| (aliases.expand(&device).to_owned(), enabled))
+                .collect(),
+        );
+    }
+
     let mute = matches.value_of_t("mute")?;
-    sbz_switch::set(
-        matches.value_of_os("device").map(HSTRING::from).as_ref(),
+    set_and_report(device_id(matches)?, &configuration, mute)
+}
+
+/// Applies a configuration and prints the changes made to other devices,
+/// even if it fails.
+fn set_and_report(
+    device: Option<HSTRING>,
+    configuration: &Configuration,
+    mute: bool,
+) -> Result<(), Box<dyn Error>> {
+    match sbz_switch::set(device.as_ref(), configuration, mute) {
+        Ok(report) => {
+            print_report(&report);
+            Ok(())
+        }
+        Err(error) => {
+            if let Some(error) = error.downcast_ref::<SetError>() {
+                print_report(&error.report);
+            }
+            Err(error)
+        }
+    }
+}
+
+fn print_report(report: &SetReport) {
+    for id in &report.enabled {
+        println!("Enabled {}", id);
+    }
+    for id in &report.disabled {
+        println!("Disabled {}", id);
+    }
+    if !report.default_for.is_empty() {
+        let roles: Vec<_> = report.default_for.iter().map(|role| role.name()).collect();
+        println!("Made default for {}", roles.join(", "));
+    }
+}
+
+fn eq_import(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut text = String::new();
+    match matches.value_of("file") {
+        Some(name) => BufReader::new(File::open(name)?).read_to_string(&mut text)?,
+        None => io::stdin().read_to_string(&mut text)?,
+    };
+    let preset = EqPreset::from_str(&text)?;
+
+    let device = device_id(matches)?;
+    let equalizer = sbz_switch::equalizer(device.as_ref(), matches.value_of("feature"))?;
+    let configuration = Configuration {
+        endpoint: None,
+        creative: Some(
+            std::iter::once((equalizer.feature.clone(), equalizer.settings(&preset))).collect(),
+        ),
+        contexts: None,
+        endpoints: None,
+    };
+
+    if matches.is_present("apply") {
+        let mute = matches.value_of_t("mute")?;
+        return set_and_report(device, &configuration, mute);
+    }
+    let output = matches.value_of("output");
+    let text = format_configuration(
         &configuration,
-        mute,
-    )
+        Format::detect(matches, output, None),
+        &FormatOptions::default(),
+    )?;
+    match output {
+        Some(name) => write!(File::create(name)?, "{}", text)?,
+        _ => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn eq_export(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let equalizer =
+        sbz_switch::equalizer(device_id(matches)?.as_ref(), matches.value_of("feature"))?;
+    let preset = equalizer.preset(!matches.is_present("graphic"));
+    match matches.value_of("output") {
+        Some(name) => write!(File::create(name)?, "{}", preset)?,
+        _ => print!("{}", preset),
+    }
+    Ok(())
 }
 
 struct Collator<I, F> {
@@ -655,15 +1530,16 @@ fn collate_set_values<I, F>(iter: Option<I>, f: F) -> Collator<I, F> {
 }
 
 fn set(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    let mut creative_table = IndexMap::<String, IndexMap<String, SoundCoreParamValue>>::new();
+    let mut creative_table =
+        IndexMap::<String, IndexMap<String, Setting<SoundCoreParamValue>>>::new();
 
     for (feature, parameter, value) in collate_set_values(matches.values_of("bool"), |s| {
         bool::from_str(s).map(SoundCoreParamValue::Bool)
     }) {
         creative_table
             .entry(feature.to_owned())
-            .or_insert_with(IndexMap::<String, SoundCoreParamValue>::new)
-            .insert(parameter.to_owned(), value?);
+            .or_default()
+            .insert(parameter.to_owned(), Setting::Absolute(value?));
     }
 
     for (feature, parameter, value) in collate_set_values(matches.values_of("float"), |s| {
@@ -671,8 +1547,8 @@ fn set(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
     }) {
         creative_table
             .entry(feature.to_owned())
-            .or_insert_with(IndexMap::<String, SoundCoreParamValue>::new)
-            .insert(parameter.to_owned(), value?);
+            .or_default()
+            .insert(parameter.to_owned(), Setting::Absolute(value?));
     }
 
     for (feature, parameter, value) in collate_set_values(matches.values_of("int"), |s| {
@@ -680,32 +1556,463 @@ fn set(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
     }) {
         creative_table
             .entry(feature.to_owned())
-            .or_insert_with(IndexMap::<String, SoundCoreParamValue>::new)
-            .insert(parameter.to_owned(), value?);
+            .or_default()
+            .insert(parameter.to_owned(), Setting::Absolute(value?));
     }
 
-    let configuration = Configuration {
+    if let Some(values) = matches.values_of("set") {
+        let catalog = Catalog::builtin();
+        for value in values {
+            let (feature, parameter, value) = parse_set_arg(&catalog, value)?;
+            creative_table
+                .entry(feature.to_owned())
+                .or_default()
+                .insert(parameter.to_owned(), value);
+        }
+    }
+
+    let mut configuration = Configuration {
         endpoint: Some(EndpointConfiguration {
             volume: matches
                 .value_of("volume")
-                .map(|s| f32::from_str(s).unwrap() / 100.0),
+                .map(parse_volume_arg)
+                .transpose()?,
+            mute: matches.value_of("muted").map(bool::from_str).transpose()?,
+            default: Vec::new(),
         }),
-        creative: Some(creative_table),
+        creative: None,
+        contexts: None,
+        endpoints: None,
     };
+    match matches.value_of("context") {
+        Some(context) => {
+            configuration.contexts =
+                Some(std::iter::once((context.to_owned(), creative_table)).collect())
+        }
+        None => configuration.creative = Some(creative_table),
+    }
 
     let mute = matches.value_of_t("mute")?;
-    sbz_switch::set(
-        matches.value_of_os("device").map(HSTRING::from).as_ref(),
-        &configuration,
-        mute,
-    )
+    set_and_report(device_id(matches)?, &configuration, mute)
+}
+
+fn parse_volume_arg(value: &str) -> Result<Setting<Volume>, ParseVolumeError> {
+    // plain numbers are percentages for compatibility with older versions
+    match f32::from_str(value) {
+        Ok(percent) if value.starts_with(['+', '-']) => {
+            Ok(Setting::Relative(Volume::Scalar(percent / 100.0)))
+        }
+        Ok(percent) => Ok(Setting::Absolute(Volume::Scalar(percent / 100.0))),
+        Err(_) => Setting::from_str(value),
+    }
+}
+
+fn parse_event_mask(arg: &str) -> Result<EventMask, std::num::ParseIntError> {
+    let mask = match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16)?,
+        None => u32::from_str(arg)?,
+    };
+    Ok(EventMask(mask))
+}
+
+#[derive(Debug)]
+struct InvalidDurationError {
+    arg: String,
+}
+
+impl fmt::Display for InvalidDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected a duration in milliseconds or seconds, like 200ms or 1.5s, got {:?}",
+            self.arg
+        )
+    }
+}
+
+impl Error for InvalidDurationError {}
+
+fn parse_duration(arg: &str) -> Result<Duration, InvalidDurationError> {
+    let invalid = || InvalidDurationError {
+        arg: arg.to_owned(),
+    };
+    let (number, scale) = match arg.trim().strip_suffix("ms") {
+        Some(number) => (number, 0.001),
+        None => match arg.trim().strip_suffix('s') {
+            Some(number) => (number, 1.0),
+            // plain numbers are milliseconds
+            None => (arg.trim(), 0.001),
+        },
+    };
+    let seconds = f64::from_str(number.trim()).map_err(|_| invalid())? * scale;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+#[derive(Debug)]
+struct InvalidSetError {
+    arg: String,
+}
+
+impl fmt::Display for InvalidSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected FEATURE.PARAMETER=VALUE, FEATURE.PARAMETER+=VALUE, FEATURE.PARAMETER-=VALUE, or ALIAS=VALUE, got {:?}",
+            self.arg
+        )
+    }
+}
+
+impl Error for InvalidSetError {}
+
+fn parse_set_arg<'a>(
+    catalog: &'a Catalog,
+    arg: &'a str,
+) -> Result<(&'a str, &'a str, Setting<SoundCoreParamValue>), Box<dyn Error>> {
+    let invalid = || InvalidSetError {
+        arg: arg.to_owned(),
+    };
+    let (path, value) = arg.split_once('=').ok_or_else(invalid)?;
+    let (path, adjustment) = if let Some(path) = path.strip_suffix('+') {
+        (path, Some("+="))
+    } else if let Some(path) = path.strip_suffix('-') {
+        (path, Some("-="))
+    } else {
+        (path, None)
+    };
+    let (feature, parameter, alias) = match path.split_once('.') {
+        Some((feature, parameter)) => (
+            feature,
+            parameter,
+            Alias::for_parameter(catalog, feature, parameter),
+        ),
+        None => {
+            let alias = Alias::find(catalog, path)?;
+            (alias.feature(), alias.parameter(), Some(alias))
+        }
+    };
+    let value = match (adjustment, alias) {
+        (Some(prefix), _) => Setting::from_str(&format!("{}{}", prefix, value))?,
+        (None, Some(alias)) => Setting::Absolute(alias.parse_value(value)?),
+        (None, None) => Setting::Absolute(SoundCoreParamValue::from_str(value)?),
+    };
+    Ok((feature, parameter, value))
+}
+
+fn set_default(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let roles = match matches.values_of("role") {
+        Some(names) => names.filter_map(DeviceRole::from_name).collect(),
+        None => DeviceRole::ALL.to_vec(),
+    };
+    sbz_switch::set_default(device_id(matches)?.as_ref(), &roles)?;
+    Ok(())
 }
 
 fn watch(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    for event in
-        sbz_switch::watch_with_volume(matches.value_of_os("device").map(HSTRING::from).as_ref())?
-    {
-        println!("{:?}", event);
+    let device = device_id(matches)?;
+    let device_id = sbz_switch::device_info(device.as_ref())?.id;
+    let format = matches.value_of("format").and_then(Format::from_name);
+    let filter = EventFilter {
+        feature: matches
+            .value_of("feature")
+            .map(Pattern::from_str)
+            .transpose()?,
+        parameter: matches
+            .value_of("parameter")
+            .map(Pattern::from_str)
+            .transpose()?,
+        volume_only: matches.is_present("volume-only"),
+        raw: matches.is_present("raw"),
+    };
+    let options = WatchOptions {
+        debounce: matches
+            .value_of("debounce")
+            .map(parse_duration)
+            .transpose()?,
+        event_mask: matches
+            .value_of("event-mask")
+            .map(parse_event_mask)
+            .transpose()?
+            .unwrap_or_default(),
+        poll: matches
+            .value_of("poll")
+            .map(parse_duration)
+            .transpose()?
+            .map(|interval| PollOptions {
+                interval,
+                feature: filter.feature.clone(),
+                parameter: filter.parameter.clone(),
+                volume: true,
+            }),
+    };
+    for event in sbz_switch::watch_with_options(device.as_ref(), &options)? {
+        let event = event?;
+        if !filter.matches(&event) {
+            continue;
+        }
+        let event = SerializableEvent::new(&device_id, event, filter.raw);
+        match format {
+            // one event per line
+            Some(Format::Json) => println!("{}", serde_json::to_string(&event)?),
+            // serde_yaml starts each document with ---
+            Some(Format::Yaml) => print!("{}", serde_yaml::to_string(&event)?),
+            Some(format) => println!("{}", format.serialize(&event)?),
+            None => println!("{}", event),
+        }
+        io::stdout().flush()?;
     }
     Ok(())
 }
+
+/// Selects the events reported by `watch`.
+struct EventFilter {
+    feature: Option<Pattern>,
+    parameter: Option<Pattern>,
+    volume_only: bool,
+    raw: bool,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &SoundCoreOrVolumeEvent) -> bool {
+        match event {
+            SoundCoreOrVolumeEvent::Volume(_) => self.feature.is_none() && self.parameter.is_none(),
+            SoundCoreOrVolumeEvent::SoundCore(SoundCoreEvent::ParamChange {
+                feature,
+                parameter,
+                ..
+            }) => {
+                !self.volume_only
+                    && self
+                        .feature
+                        .as_ref()
+                        .map_or(true, |pattern| pattern.matches(&feature.description))
+                    && self
+                        .parameter
+                        .as_ref()
+                        .map_or(true, |pattern| pattern.matches(&parameter.description))
+            }
+            SoundCoreOrVolumeEvent::SoundCore(SoundCoreEvent::Unknown(_)) if !self.raw => false,
+            _ => !self.volume_only && self.feature.is_none() && self.parameter.is_none(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SerializableEvent {
+    timestamp: String,
+    device: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parameter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<SerdeParamValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old: Option<SerdeParamValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    muted: Option<bool>,
+    /// The device a topology event is about, which may not be the watched device
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flow: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    property: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw: Option<SerializableEventInfo>,
+}
+
+/// The codes of a SoundCore event, as reported by the driver.
+#[derive(Serialize)]
+struct SerializableEventInfo {
+    event: u32,
+    data: u32,
+    param: u32,
+}
+
+impl From<EventInfo> for SerializableEventInfo {
+    fn from(info: EventInfo) -> Self {
+        SerializableEventInfo {
+            event: info.event,
+            data: info.data_or_feature_id,
+            param: info.param_id,
+        }
+    }
+}
+
+impl SerializableEvent {
+    fn new(device: &str, event: SoundCoreOrVolumeEvent, raw: bool) -> Self {
+        let mut value = SerializableEvent {
+            timestamp: timestamp(SystemTime::now()),
+            device: device.to_owned(),
+            kind: "",
+            feature: None,
+            parameter: None,
+            value: None,
+            old: None,
+            volume: None,
+            muted: None,
+            id: None,
+            state: None,
+            flow: None,
+            role: None,
+            property: None,
+            context: None,
+            raw: None,
+        };
+        match event {
+            SoundCoreOrVolumeEvent::SoundCore(SoundCoreEvent::ParamChange {
+                feature,
+                parameter,
+                old,
+                new,
+            }) => {
+                value.kind = "parameter";
+                value.old = old.map(|old| SerdeParamValue::new(&Setting::Absolute(old), false));
+                value.value = Some(SerdeParamValue::new(&Setting::Absolute(new), false));
+                value.feature = Some(feature.description);
+                value.parameter = Some(parameter.description);
+            }
+            SoundCoreOrVolumeEvent::SoundCore(SoundCoreEvent::ContextChange { info, context }) => {
+                value.kind = "context";
+                value.context = Some(context.description);
+                value.raw = raw.then(|| info.into());
+            }
+            SoundCoreOrVolumeEvent::SoundCore(SoundCoreEvent::FeatureEvent {
+                info,
+                feature,
+                parameter,
+            }) => {
+                value.kind = "feature";
+                value.feature = Some(feature.description);
+                value.parameter = parameter.map(|parameter| parameter.description);
+                value.raw = raw.then(|| info.into());
+            }
+            SoundCoreOrVolumeEvent::SoundCore(SoundCoreEvent::Unknown(info)) => {
+                value.kind = "unknown";
+                value.raw = Some(info.into());
+            }
+            SoundCoreOrVolumeEvent::Volume(notification) => {
+                value.kind = "volume";
+                value.volume = Some(notification.volume);
+                value.muted = Some(notification.is_muted);
+            }
+            SoundCoreOrVolumeEvent::DeviceAdded { id } => {
+                value.kind = "device-added";
+                value.id = Some(id);
+            }
+            SoundCoreOrVolumeEvent::DeviceRemoved { id } => {
+                value.kind = "device-removed";
+                value.id = Some(id);
+            }
+            SoundCoreOrVolumeEvent::DeviceStateChanged { id, state } => {
+                value.kind = "device-state";
+                value.id = Some(id);
+                value.state = Some(state.name());
+            }
+            SoundCoreOrVolumeEvent::DefaultDeviceChanged { flow, role, id } => {
+                value.kind = "default-device";
+                value.id = id;
+                value.flow = Some(flow.name());
+                value.role = Some(role.name());
+            }
+            SoundCoreOrVolumeEvent::PropertyChanged { id, key } => {
+                value.kind = "property";
+                value.id = Some(id);
+                value.property = Some(format!("{:?} {}", key.fmtid, key.pid));
+            }
+        }
+        value
+    }
+}
+
+impl fmt::Display for SerializableEvent {
+    /// Writes the event as a line of text, e.g.
+    /// `2024-01-31T12:34:56.789Z parameter EfxMasterControl.Bass Level = 0.5`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.timestamp, self.kind)?;
+        match (&self.feature, &self.parameter) {
+            (Some(feature), Some(parameter)) => write!(f, " {}.{}", feature, parameter)?,
+            (Some(feature), None) => write!(f, " {}", feature)?,
+            _ => {}
+        }
+        if let Some(context) = &self.context {
+            write!(f, " {}", context)?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " = {}", value)?;
+        }
+        if let Some(old) = &self.old {
+            write!(f, " (was {})", old)?;
+        }
+        if let Some(volume) = self.volume {
+            write!(f, " {}", volume)?;
+        }
+        if self.muted == Some(true) {
+            f.write_str(" muted")?;
+        }
+        for text in [self.flow, self.role, self.state].iter().flatten() {
+            write!(f, " {}", text)?;
+        }
+        if let Some(id) = &self.id {
+            write!(f, " {}", id)?;
+        }
+        if let Some(property) = &self.property {
+            write!(f, " {}", property)?;
+        }
+        if let Some(raw) = &self.raw {
+            write!(
+                f,
+                " [event {} data {} param {}]",
+                raw.event, raw.data, raw.param
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a time as an RFC 3339 timestamp in UTC, e.g. `2024-01-31T12:34:56.789Z`.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let time_of_day = seconds % 86400;
+    // converts days since 1970-01-01 to a date in the proleptic Gregorian
+    // calendar, counting eras of 400 years from 0000-03-01
+    let days = seconds / 86400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
@@ -2,13 +2,16 @@ use futures::channel::mpsc;
 use futures::Stream;
 use windows::core::GUID;
 use windows::Win32::Media::Audio::Endpoints::{IAudioEndpointVolume, IAudioEndpointVolumeCallback};
+use windows::Win32::Media::Audio::{IMMDeviceEnumerator, IMMNotificationClient};
+use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
 
 use std::fmt;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use super::AudioEndpointVolumeCallback;
+use super::{AudioEndpointVolumeCallback, DataFlow, DeviceRole, DeviceState, MMNotificationClient};
 use crate::com::ComObject;
+use crate::debounce::Coalesce;
 
 /// Describes a volume change event.
 ///
@@ -48,10 +51,21 @@ impl fmt::Debug for VolumeNotification {
     }
 }
 
+impl Coalesce for VolumeNotification {
+    /// Every volume notification replaces the previous one.
+    type Key = ();
+
+    fn coalesce_key(&self) -> Option<Self::Key> {
+        Some(())
+    }
+}
+
 pub(crate) struct VolumeEvents {
     volume: ComObject<IAudioEndpointVolume>,
     events: mpsc::UnboundedReceiver<VolumeNotification>,
     callback: ComObject<IAudioEndpointVolumeCallback>,
+    /// The last volume and mute state seen, once the volume has been polled
+    last: Option<(f32, bool)>,
 }
 
 impl VolumeEvents {
@@ -68,16 +82,54 @@ impl VolumeEvents {
                 volume,
                 events: rx,
                 callback: ComObject::take(callback),
+                last: None,
             })
         }
     }
+
+    /// Reads the volume and mute state, and reports them if they are not
+    /// the same as when they were last seen.
+    ///
+    /// After the first call, notifications that do not change the volume or
+    /// mute state are no longer reported.
+    pub fn poll_volume(&mut self) -> windows::core::Result<Option<VolumeNotification>> {
+        let (volume, is_muted) = unsafe {
+            (
+                self.volume.GetMasterVolumeLevelScalar()?,
+                self.volume.GetMute()?.into(),
+            )
+        };
+        match self.last.replace((volume, is_muted)) {
+            Some(last) if last != (volume, is_muted) => Ok(Some(VolumeNotification {
+                event_context: GUID::zeroed(),
+                is_muted,
+                volume,
+            })),
+            // the first reading is only a baseline
+            _ => Ok(None),
+        }
+    }
 }
 
 impl Stream for VolumeEvents {
     type Item = VolumeNotification;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.events).poll_next(cx)
+        loop {
+            match Pin::new(&mut self.events).poll_next(cx) {
+                Poll::Ready(Some(event)) => {
+                    if let Some(last) = self.last {
+                        // already reported by polling
+                        if last == (event.volume, event.is_muted) {
+                            continue;
+                        }
+                        self.last = Some((event.volume, event.is_muted));
+                    }
+                    return Poll::Ready(Some(event));
+                }
+                other => return other,
+            }
+        }
     }
 }
 
@@ -90,3 +142,85 @@ impl Drop for VolumeEvents {
         }
     }
 }
+
+/// Describes a change to the audio devices of the system.
+///
+/// [Official documentation](https://docs.microsoft.com/en-us/windows/win32/api/mmdeviceapi/nn-mmdeviceapi-immnotificationclient)
+#[derive(Debug)]
+pub enum DeviceNotification {
+    /// A device was added.
+    Added {
+        /// The id of the device
+        id: String,
+    },
+    /// A device was removed.
+    Removed {
+        /// The id of the device
+        id: String,
+    },
+    /// A device was enabled, disabled, unplugged, or plugged in.
+    StateChanged {
+        /// The id of the device
+        id: String,
+        /// The new state of the device
+        state: DeviceState,
+    },
+    /// The default device for a role changed.
+    DefaultChanged {
+        /// Whether the default output or input changed
+        flow: DataFlow,
+        /// The role the device is now the default for
+        role: DeviceRole,
+        /// The id of the new default device, if there is one
+        id: Option<String>,
+    },
+    /// A property of a device changed.
+    PropertyChanged {
+        /// The id of the device
+        id: String,
+        /// The property that changed
+        key: PROPERTYKEY,
+    },
+}
+
+pub(crate) struct DeviceEvents {
+    enumerator: ComObject<IMMDeviceEnumerator>,
+    events: mpsc::UnboundedReceiver<DeviceNotification>,
+    client: ComObject<IMMNotificationClient>,
+}
+
+impl DeviceEvents {
+    pub fn new(enumerator: ComObject<IMMDeviceEnumerator>) -> windows::core::Result<Self> {
+        let (tx, rx) = mpsc::unbounded();
+
+        unsafe {
+            let client: IMMNotificationClient = MMNotificationClient::new(tx).into();
+
+            (*enumerator).RegisterEndpointNotificationCallback(&client)?;
+
+            Ok(Self {
+                enumerator,
+                events: rx,
+                client: ComObject::take(client),
+            })
+        }
+    }
+}
+
+impl Stream for DeviceEvents {
+    type Item = DeviceNotification;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+impl Drop for DeviceEvents {
+    fn drop(&mut self) {
+        unsafe {
+            (*self.enumerator)
+                .UnregisterEndpointNotificationCallback(&*self.client)
+                .unwrap();
+        }
+    }
+}
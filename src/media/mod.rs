@@ -16,14 +16,16 @@ use futures::channel::mpsc::UnboundedSender;
 use futures::{executor, SinkExt};
 use regex::Regex;
 use tracing::{info, instrument};
-use windows::core::{implement, GUID, PCWSTR};
+use windows::core::{implement, Interface, GUID, HSTRING, PCWSTR};
 use windows::Win32::Foundation::E_ABORT;
 use windows::Win32::Media::Audio::Endpoints::{
     IAudioEndpointVolume, IAudioEndpointVolumeCallback, IAudioEndpointVolumeCallback_Impl,
 };
 use windows::Win32::Media::Audio::{
-    eConsole, eRender, IMMDevice, IMMDeviceEnumerator, MMDeviceEnumerator,
-    AUDIO_VOLUME_NOTIFICATION_DATA, DEVICE_STATE_ACTIVE,
+    eAll, eCapture, eCommunications, eConsole, eMultimedia, eRender, EDataFlow, ERole, IMMDevice,
+    IMMDeviceEnumerator, IMMEndpoint, IMMNotificationClient, IMMNotificationClient_Impl,
+    MMDeviceEnumerator, AUDIO_VOLUME_NOTIFICATION_DATA, DEVICE_STATEMASK_ALL, DEVICE_STATE_ACTIVE,
+    DEVICE_STATE_DISABLED, DEVICE_STATE_NOTPRESENT, DEVICE_STATE_UNPLUGGED,
 };
 use windows::Win32::System::Com::StructuredStorage::{PropVariantClear, PROPVARIANT};
 use windows::Win32::System::Com::{
@@ -31,10 +33,11 @@ use windows::Win32::System::Com::{
 };
 use windows::Win32::UI::Shell::PropertiesSystem::{IPropertyStore, PROPERTYKEY};
 
-pub(crate) use self::event::VolumeEvents;
-pub use self::event::VolumeNotification;
+pub(crate) use self::event::{DeviceEvents, VolumeEvents};
+pub use self::event::{DeviceNotification, VolumeNotification};
 use crate::com::{ComObject, ComScope};
 use crate::lazy::Lazy;
+use crate::policyconfig::{CLSID_PolicyConfigClient, IPolicyConfig};
 use crate::soundcore::{SoundCoreError, PKEY_SOUNDCORECTL_CLSID_AE5, PKEY_SOUNDCORECTL_CLSID_Z};
 use crate::winapiext::{PKEY_DeviceInterface_FriendlyName, PKEY_Device_DeviceDesc};
 
@@ -130,14 +133,35 @@ impl Endpoint {
     ///
     /// This allows discovery of a SoundCore implementation for devices that support it.
     pub fn clsid(&self) -> Result<GUID, SoundCoreError> {
+        self.clsid_with_source().map(|(clsid, _)| clsid)
+    }
+    /// Gets the CLSID of the class implementing Creative's APIs, and the
+    /// property it was found in.
+    pub fn clsid_with_source(&self) -> Result<(GUID, ClsidSource), SoundCoreError> {
         let store = self.property_store()?;
-        let value = match store.get_string_value(&PKEY_SOUNDCORECTL_CLSID_AE5)? {
-            Some(value) => value,
-            None => store
-                .get_string_value(&PKEY_SOUNDCORECTL_CLSID_Z)?
-                .ok_or(SoundCoreError::NotSupported)?,
+        let (value, source) = match store.get_string_value(&PKEY_SOUNDCORECTL_CLSID_AE5)? {
+            Some(value) => (value, ClsidSource::AE5),
+            None => (
+                store
+                    .get_string_value(&PKEY_SOUNDCORECTL_CLSID_Z)?
+                    .ok_or(SoundCoreError::NotSupported)?,
+                ClsidSource::Z,
+            ),
         };
-        parse_guid(&value).or(Err(SoundCoreError::NotSupported))
+        let clsid = parse_guid(&value).or(Err(SoundCoreError::NotSupported))?;
+        Ok((clsid, source))
+    }
+    /// Gets the state of the device.
+    pub fn state(&self) -> windows::core::Result<DeviceState> {
+        let state = unsafe { self.device.GetState()? };
+        Ok(DeviceState::from_windows(state))
+    }
+    /// Gets whether the device plays or records sound.
+    pub fn flow(&self) -> windows::core::Result<DataFlow> {
+        unsafe {
+            let endpoint: IMMEndpoint = self.device.cast()?;
+            Ok(DataFlow::from_windows(endpoint.GetDataFlow()?))
+        }
     }
 
     /// Gets the friendly name of the audio interface (sound adapter).
@@ -200,11 +224,60 @@ impl Endpoint {
             Ok(volume)
         }
     }
+    /// Sets the volume of the device, in decibels.
+    ///
+    /// The volume must be within the range reported by
+    /// [`get_volume_range`](#method.get_volume_range).
+    #[instrument]
+    pub fn set_volume_db(&self, volume: f32) -> windows::core::Result<()> {
+        unsafe {
+            info!("Setting volume to {volume}dB...");
+            self.volume()?
+                .SetMasterVolumeLevel(volume, &GUID::zeroed())?;
+            Ok(())
+        }
+    }
+    /// Gets the volume of the device, in decibels.
+    #[instrument(level = "debug", fields(volume))]
+    pub fn get_volume_db(&self) -> windows::core::Result<f32> {
+        unsafe {
+            let volume = self.volume()?.GetMasterVolumeLevel()?;
+            tracing::Span::current().record("volume", volume);
+            Ok(volume)
+        }
+    }
+    /// Gets the range of volume levels supported by the device, in decibels.
+    #[instrument(level = "debug")]
+    pub fn get_volume_range(&self) -> windows::core::Result<VolumeRange> {
+        unsafe {
+            let mut range = VolumeRange {
+                min: 0.0,
+                max: 0.0,
+                increment: 0.0,
+            };
+            self.volume()?
+                .GetVolumeRange(&mut range.min, &mut range.max, &mut range.increment)?;
+            Ok(range)
+        }
+    }
     pub(crate) fn event_stream(&self) -> windows::core::Result<VolumeEvents> {
         VolumeEvents::new(self.volume()?)
     }
 }
 
+/// Describes the range of volume levels supported by a device.
+///
+/// See [IAudioEndpointVolume::GetVolumeRange](https://docs.microsoft.com/en-us/windows/win32/api/endpointvolume/nf-endpointvolume-iaudioendpointvolume-getvolumerange).
+#[derive(Clone, Copy, Debug)]
+pub struct VolumeRange {
+    /// The minimum volume level, in decibels.
+    pub min: f32,
+    /// The maximum volume level, in decibels.
+    pub max: f32,
+    /// The volume increment, in decibels.
+    pub increment: f32,
+}
+
 impl fmt::Debug for Endpoint {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Endpoint")
@@ -289,6 +362,158 @@ impl PropertyStore {
     }
 }
 
+/// Identifies the property a SoundCore CLSID was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClsidSource {
+    /// The property used by the Sound Blaster Z series
+    Z,
+    /// The property used by the Sound Blaster AE-5 and newer devices
+    AE5,
+}
+
+impl ClsidSource {
+    /// Gets the name of the property, e.g. `ae5`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ClsidSource::Z => "z",
+            ClsidSource::AE5 => "ae5",
+        }
+    }
+}
+
+/// Describes whether a device can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceState {
+    /// The device is present and enabled
+    Active,
+    /// The device has been disabled
+    Disabled,
+    /// The device has been removed
+    NotPresent,
+    /// Nothing is plugged into the jack of the device
+    Unplugged,
+    /// The device has a state this crate does not recognize
+    Unknown(u32),
+}
+
+impl DeviceState {
+    /// Gets the name of the state, e.g. `not present`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DeviceState::Active => "active",
+            DeviceState::Disabled => "disabled",
+            DeviceState::NotPresent => "not present",
+            DeviceState::Unplugged => "unplugged",
+            DeviceState::Unknown(_) => "unknown",
+        }
+    }
+
+    fn from_windows(state: u32) -> Self {
+        match state {
+            DEVICE_STATE_ACTIVE => DeviceState::Active,
+            DEVICE_STATE_DISABLED => DeviceState::Disabled,
+            DEVICE_STATE_NOTPRESENT => DeviceState::NotPresent,
+            DEVICE_STATE_UNPLUGGED => DeviceState::Unplugged,
+            _ => DeviceState::Unknown(state),
+        }
+    }
+}
+
+/// Describes whether a device plays or records sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFlow {
+    /// The device plays sound
+    Render,
+    /// The device records sound
+    Capture,
+}
+
+impl DataFlow {
+    /// Gets the name of the flow, e.g. `render`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DataFlow::Render => "render",
+            DataFlow::Capture => "capture",
+        }
+    }
+
+    fn to_windows(self) -> EDataFlow {
+        match self {
+            DataFlow::Render => eRender,
+            DataFlow::Capture => eCapture,
+        }
+    }
+
+    fn from_windows(flow: EDataFlow) -> Self {
+        if flow == eCapture {
+            DataFlow::Capture
+        } else {
+            DataFlow::Render
+        }
+    }
+}
+
+/// Describes what a default audio device is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceRole {
+    /// Games, system notification sounds, and voice commands
+    Console,
+    /// Music, movies, narration, and live music recording
+    Multimedia,
+    /// Voice communications
+    Communications,
+}
+
+impl DeviceRole {
+    /// All roles, in the order Windows defines them.
+    pub const ALL: [DeviceRole; 3] = [
+        DeviceRole::Console,
+        DeviceRole::Multimedia,
+        DeviceRole::Communications,
+    ];
+
+    /// Finds the role with a name, ignoring case.
+    pub fn from_name(name: &str) -> Option<DeviceRole> {
+        DeviceRole::ALL
+            .iter()
+            .copied()
+            .find(|role| role.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Gets the name of the role, e.g. `communications`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DeviceRole::Console => "console",
+            DeviceRole::Multimedia => "multimedia",
+            DeviceRole::Communications => "communications",
+        }
+    }
+
+    fn to_windows(self) -> ERole {
+        match self {
+            DeviceRole::Console => eConsole,
+            DeviceRole::Multimedia => eMultimedia,
+            DeviceRole::Communications => eCommunications,
+        }
+    }
+
+    fn from_windows(role: ERole) -> Self {
+        if role == eMultimedia {
+            DeviceRole::Multimedia
+        } else if role == eCommunications {
+            DeviceRole::Communications
+        } else {
+            DeviceRole::Console
+        }
+    }
+}
+
+impl fmt::Display for DeviceRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// Provides access to the devices available in the current Windows session.
 #[derive(Debug)]
 pub struct DeviceEnumerator(ComObject<IMMDeviceEnumerator>);
@@ -304,11 +529,29 @@ impl DeviceEnumerator {
         }
     }
     /// Gets all active audio outputs.
+    pub fn get_active_audio_endpoints(&self) -> windows::core::Result<Vec<Endpoint>> {
+        self.get_audio_endpoints(Some(DataFlow::Render), false)
+    }
+    /// Gets audio endpoints.
+    ///
+    /// If `flow` is `None`, both outputs and inputs are included. If
+    /// `include_inactive` is true, devices that are disabled, unplugged, or
+    /// not present are included as well.
     #[allow(clippy::unnecessary_mut_passed)]
     #[instrument(level = "trace")]
-    pub fn get_active_audio_endpoints(&self) -> windows::core::Result<Vec<Endpoint>> {
+    pub fn get_audio_endpoints(
+        &self,
+        flow: Option<DataFlow>,
+        include_inactive: bool,
+    ) -> windows::core::Result<Vec<Endpoint>> {
         unsafe {
-            let collection = self.0.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+            let flow = flow.map_or(eAll, DataFlow::to_windows);
+            let states = if include_inactive {
+                DEVICE_STATEMASK_ALL
+            } else {
+                DEVICE_STATE_ACTIVE
+            };
+            let collection = self.0.EnumAudioEndpoints(flow, states)?;
             let count = collection.GetCount()?;
             let mut result = Vec::with_capacity(count as usize);
             for i in 0..count {
@@ -323,10 +566,27 @@ impl DeviceEnumerator {
     /// There are multiple default audio outputs in Windows.
     /// This function gets the device that would be used if the current application
     /// were to play music or sound effects (as opposed to VOIP audio).
-    #[instrument(level = "trace", fields(id))]
     pub fn get_default_audio_endpoint(&self) -> windows::core::Result<Endpoint> {
+        self.get_default_audio_endpoint_for_role(DeviceRole::Console)
+    }
+    /// Gets the default audio output for a role.
+    pub fn get_default_audio_endpoint_for_role(
+        &self,
+        role: DeviceRole,
+    ) -> windows::core::Result<Endpoint> {
+        self.get_default_endpoint(DataFlow::Render, role)
+    }
+    /// Gets the default audio output or input for a role.
+    #[instrument(level = "trace", fields(id))]
+    pub fn get_default_endpoint(
+        &self,
+        flow: DataFlow,
+        role: DeviceRole,
+    ) -> windows::core::Result<Endpoint> {
         unsafe {
-            let device = self.0.GetDefaultAudioEndpoint(eRender, eConsole)?;
+            let device = self
+                .0
+                .GetDefaultAudioEndpoint(flow.to_windows(), role.to_windows())?;
             let endpoint = Endpoint::new(ComObject::take(device));
 
             let span = tracing::Span::current();
@@ -353,6 +613,50 @@ impl DeviceEnumerator {
             Ok(Endpoint::new(ComObject::take(device)))
         }
     }
+
+    pub(crate) fn event_stream(&self) -> windows::core::Result<DeviceEvents> {
+        DeviceEvents::new(self.0.clone())
+    }
+}
+
+/// Changes the system-wide configuration of audio devices.
+///
+/// This uses the undocumented interface behind the Windows sound control
+/// panel.
+#[derive(Debug)]
+pub struct PolicyConfig(ComObject<IPolicyConfig>);
+
+impl PolicyConfig {
+    /// Creates a new policy configuration client.
+    #[instrument(level = "trace")]
+    pub fn new() -> windows::core::Result<Self> {
+        unsafe {
+            let _scope = ComScope::begin();
+            let policy = CoCreateInstance(&CLSID_PolicyConfigClient, None, CLSCTX_ALL)?;
+            Ok(PolicyConfig(ComObject::take(policy)))
+        }
+    }
+    /// Makes a device the default device for a role.
+    #[instrument(level = "trace", skip(self))]
+    pub fn set_default_endpoint(&self, id: &str, role: DeviceRole) -> windows::core::Result<()> {
+        unsafe {
+            self.0
+                .SetDefaultEndpoint((&HSTRING::from(id)).into(), role.to_windows())
+                .ok()
+        }
+    }
+    /// Enables or disables a device.
+    ///
+    /// Disabled devices are hidden from applications and from the default
+    /// device choices, like disabling them in the Sound control panel.
+    #[instrument(level = "trace", skip(self))]
+    pub fn set_endpoint_visibility(&self, id: &str, visible: bool) -> windows::core::Result<()> {
+        unsafe {
+            self.0
+                .SetEndpointVisibility((&HSTRING::from(id)).into(), visible.into())
+                .ok()
+        }
+    }
 }
 
 #[implement(IAudioEndpointVolumeCallback)]
@@ -382,3 +686,69 @@ impl IAudioEndpointVolumeCallback_Impl for AudioEndpointVolumeCallback {
         }
     }
 }
+
+#[implement(IMMNotificationClient)]
+pub(crate) struct MMNotificationClient {
+    sender: Mutex<UnboundedSender<DeviceNotification>>,
+}
+
+impl MMNotificationClient {
+    unsafe fn new(sender: UnboundedSender<DeviceNotification>) -> Self {
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    fn send(&self, notification: DeviceNotification) -> windows::core::Result<()> {
+        match executor::block_on(self.sender.lock().unwrap().send(notification)) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(windows::core::Error::from(E_ABORT)),
+        }
+    }
+}
+
+fn device_id(id: &PCWSTR) -> String {
+    unsafe { String::from_utf16_lossy(id.as_wide()) }
+}
+
+impl IMMNotificationClient_Impl for MMNotificationClient {
+    fn OnDeviceStateChanged(&self, id: &PCWSTR, state: u32) -> windows::core::Result<()> {
+        self.send(DeviceNotification::StateChanged {
+            id: device_id(id),
+            state: DeviceState::from_windows(state),
+        })
+    }
+
+    fn OnDeviceAdded(&self, id: &PCWSTR) -> windows::core::Result<()> {
+        self.send(DeviceNotification::Added { id: device_id(id) })
+    }
+
+    fn OnDeviceRemoved(&self, id: &PCWSTR) -> windows::core::Result<()> {
+        self.send(DeviceNotification::Removed { id: device_id(id) })
+    }
+
+    fn OnDefaultDeviceChanged(
+        &self,
+        flow: EDataFlow,
+        role: ERole,
+        id: &PCWSTR,
+    ) -> windows::core::Result<()> {
+        self.send(DeviceNotification::DefaultChanged {
+            flow: DataFlow::from_windows(flow),
+            role: DeviceRole::from_windows(role),
+            // there is no default device when the last one is removed
+            id: if id.is_null() {
+                None
+            } else {
+                Some(device_id(id))
+            },
+        })
+    }
+
+    fn OnPropertyValueChanged(&self, id: &PCWSTR, key: &PROPERTYKEY) -> windows::core::Result<()> {
+        self.send(DeviceNotification::PropertyChanged {
+            id: device_id(id),
+            key: *key,
+        })
+    }
+}
@@ -0,0 +1,64 @@
+//! Undocumented COM API used by the Windows sound control panel.
+//!
+//! This is based on the `IPolicyConfig` interface of the `PolicyConfigClient`
+//! class, which has been stable since Windows 7. Only the methods used by
+//! this crate have meaningful signatures.
+
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use std::ffi::c_void;
+
+use windows::core::interface;
+use windows::core::IUnknown;
+use windows::core::IUnknown_Vtbl;
+use windows::core::GUID;
+use windows::core::HRESULT;
+use windows::core::PCWSTR;
+use windows::Win32::Media::Audio::ERole;
+
+pub(crate) const CLSID_PolicyConfigClient: GUID =
+    GUID::from_u128(0x870af99c_171d_4f9e_af0d_e63df40c2bc9);
+
+#[interface("f8679f50-850a-41cf-9c72-430f290290c8")]
+pub(crate) unsafe trait IPolicyConfig: IUnknown {
+    pub fn GetMixFormat(&self, device_id: PCWSTR, format: *mut *mut c_void) -> HRESULT;
+    pub fn GetDeviceFormat(
+        &self,
+        device_id: PCWSTR,
+        default: i32,
+        format: *mut *mut c_void,
+    ) -> HRESULT;
+    pub fn ResetDeviceFormat(&self, device_id: PCWSTR) -> HRESULT;
+    pub fn SetDeviceFormat(
+        &self,
+        device_id: PCWSTR,
+        endpoint_format: *mut c_void,
+        mix_format: *mut c_void,
+    ) -> HRESULT;
+    pub fn GetProcessingPeriod(
+        &self,
+        device_id: PCWSTR,
+        default: i32,
+        default_period: *mut i64,
+        minimum_period: *mut i64,
+    ) -> HRESULT;
+    pub fn SetProcessingPeriod(&self, device_id: PCWSTR, period: *mut i64) -> HRESULT;
+    pub fn GetShareMode(&self, device_id: PCWSTR, mode: *mut c_void) -> HRESULT;
+    pub fn SetShareMode(&self, device_id: PCWSTR, mode: *mut c_void) -> HRESULT;
+    pub fn GetPropertyValue(
+        &self,
+        device_id: PCWSTR,
+        key: *const c_void,
+        value: *mut c_void,
+    ) -> HRESULT;
+    pub fn SetPropertyValue(
+        &self,
+        device_id: PCWSTR,
+        key: *const c_void,
+        value: *mut c_void,
+    ) -> HRESULT;
+    pub fn SetDefaultEndpoint(&self, device_id: PCWSTR, role: ERole) -> HRESULT;
+    pub fn SetEndpointVisibility(&self, device_id: PCWSTR, visible: i32) -> HRESULT;
+}
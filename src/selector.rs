@@ -0,0 +1,431 @@
+//! Selects a device by something other than its id.
+//!
+//! A [`DeviceSelector`](enum.DeviceSelector.html) is written as a prefix and
+//! a value:
+//!
+//! * `name:Speakers*` matches the description of the device, or its full
+//!   name like `Speakers (Sound Blaster Z)`
+//! * `interface:/AE-5/` matches the name of the audio interface
+//! * `role:communications` selects the default device for a role
+//! * `id:{0.0.0.00000000}.{...}` or just the id selects a specific device
+//!
+//! Names and interfaces are matched by glob patterns with `*` and `?`,
+//! ignoring case, or by regular expressions between slashes.
+//!
+//! [`DeviceAliases`](struct.DeviceAliases.html) gives short names to
+//! selectors.
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use regex::{Regex, RegexBuilder};
+use serde_derive::Deserialize;
+use tracing::{debug, warn};
+
+use crate::media::{DataFlow, DeviceEnumerator, DeviceRole, DeviceState};
+use crate::DeviceInfo;
+
+/// Selects a device.
+#[derive(Clone, Debug)]
+pub enum DeviceSelector {
+    /// Selects the device with an id
+    Id(String),
+    /// Selects the device whose description or full name matches a pattern
+    Name(Pattern),
+    /// Selects the device whose audio interface matches a pattern
+    Interface(Pattern),
+    /// Selects the default device for a role
+    Role(DeviceRole),
+}
+
+/// Matches text by glob or regular expression.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Checks whether some text matches the pattern.
+    pub fn matches(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = SelectorError;
+
+    /// Parses a regular expression between slashes, or a glob pattern.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let regex = match s.strip_prefix('/').and_then(|s| s.strip_suffix('/')) {
+            Some(expression) => Regex::new(expression),
+            None => {
+                let mut expression = String::from("^");
+                for c in s.chars() {
+                    match c {
+                        '*' => expression.push_str(".*"),
+                        '?' => expression.push('.'),
+                        c => expression.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+                    }
+                }
+                expression.push('$');
+                RegexBuilder::new(&expression)
+                    .case_insensitive(true)
+                    .build()
+            }
+        };
+        Ok(Pattern {
+            source: s.to_owned(),
+            regex: regex.map_err(SelectorError::InvalidPattern)?,
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for DeviceSelector {
+    type Err = SelectorError;
+
+    /// Parses a selector.
+    ///
+    /// Text without a `:` is a device id.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) => (kind, unquote(value.trim())),
+            None => return Ok(DeviceSelector::Id(s.to_owned())),
+        };
+        Ok(match &kind.to_ascii_lowercase()[..] {
+            "id" => DeviceSelector::Id(value.to_owned()),
+            "name" => DeviceSelector::Name(value.parse()?),
+            "interface" => DeviceSelector::Interface(value.parse()?),
+            "role" => DeviceSelector::Role(
+                DeviceRole::from_name(value)
+                    .ok_or_else(|| SelectorError::UnknownRole(value.to_owned()))?,
+            ),
+            _ => return Err(SelectorError::UnknownKind(kind.to_owned())),
+        })
+    }
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+impl fmt::Display for DeviceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeviceSelector::Id(id) => f.write_str(id),
+            DeviceSelector::Name(pattern) => write!(f, "name:{}", pattern),
+            DeviceSelector::Interface(pattern) => write!(f, "interface:{}", pattern),
+            DeviceSelector::Role(role) => write!(f, "role:{}", role),
+        }
+    }
+}
+
+impl DeviceSelector {
+    /// Finds the id of the selected device.
+    ///
+    /// Roles, names, and interfaces select among the outputs or the inputs
+    /// depending on `flow`. Names and interfaces must match exactly one
+    /// active device.
+    pub fn resolve(&self, flow: DataFlow) -> Result<String, Box<dyn Error>> {
+        self.resolve_with(Some(flow), false)
+    }
+
+    /// Finds the id of the selected device among outputs, inputs, or both.
+    ///
+    /// With `include_inactive`, names and interfaces also match disabled and
+    /// unplugged devices, but not devices that have been removed. Roles
+    /// select the default output if `flow` is `None`.
+    pub fn resolve_with(
+        &self,
+        flow: Option<DataFlow>,
+        include_inactive: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let pattern = match self {
+            DeviceSelector::Id(id) => return Ok(id.clone()),
+            DeviceSelector::Role(role) => {
+                return Ok(DeviceEnumerator::new()?
+                    .get_default_endpoint(flow.unwrap_or(DataFlow::Render), *role)?
+                    .id()?)
+            }
+            DeviceSelector::Name(pattern) | DeviceSelector::Interface(pattern) => pattern,
+        };
+        let mut found = Vec::new();
+        for endpoint in DeviceEnumerator::new()?.get_audio_endpoints(flow, include_inactive)? {
+            let device = match DeviceInfo::new(&endpoint, &[]) {
+                Ok(device) => device,
+                Err(error) => {
+                    warn!(error = %error, "Could not query device; skipping it");
+                    continue;
+                }
+            };
+            if device.state == DeviceState::NotPresent {
+                continue;
+            }
+            // a device whose names cannot be read can only be selected by id
+            let matched = match (self, &device.description, &device.interface) {
+                (DeviceSelector::Name(_), Some(description), interface) => {
+                    pattern.matches(description)
+                        || interface.as_ref().map_or(false, |interface| {
+                            pattern.matches(&format!("{} ({})", description, interface))
+                        })
+                }
+                (DeviceSelector::Interface(_), _, Some(interface)) => pattern.matches(interface),
+                _ => false,
+            };
+            if matched {
+                debug!(id = %device.id, selector = %self, "Device matches");
+                found.push(device);
+            }
+        }
+        match found.len() {
+            1 => Ok(found.pop().unwrap().id),
+            0 => Err(Box::new(SelectorError::NoMatch(self.to_string()))),
+            _ => Err(Box::new(SelectorError::MultipleMatches {
+                selector: self.to_string(),
+                devices: found
+                    .into_iter()
+                    .map(|device| {
+                        format!(
+                            "{} ({}) {}",
+                            device.description.as_deref().unwrap_or("?"),
+                            device.interface.as_deref().unwrap_or("?"),
+                            device.id
+                        )
+                    })
+                    .collect(),
+            })),
+        }
+    }
+}
+
+/// Maps short names to device selectors.
+///
+/// Aliases are read from a TOML file with a `devices` table:
+///
+/// ```toml
+/// [devices]
+/// card = "interface:/AE-5/"
+/// headset = "{0.0.0.00000000}.{8e2b1b5e-...}"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct DeviceAliases {
+    /// The selector for each alias
+    #[serde(default)]
+    pub devices: IndexMap<String, String>,
+}
+
+impl DeviceAliases {
+    /// Gets the path of the alias file.
+    ///
+    /// This is `SBZ_SWITCH_DEVICES` if it is set, and otherwise
+    /// `%APPDATA%\sbz-switch\devices.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        match env::var_os("SBZ_SWITCH_DEVICES") {
+            Some(path) => Some(path.into()),
+            None => env::var_os("APPDATA")
+                .map(|path| Path::new(&path).join("sbz-switch").join("devices.toml")),
+        }
+    }
+
+    /// Loads aliases from a file.
+    ///
+    /// A missing file has no aliases.
+    pub fn load(path: &Path) -> Result<DeviceAliases, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(toml::from_str(&text)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(DeviceAliases::default()),
+            Err(error) => Err(Box::new(error)),
+        }
+    }
+
+    /// Replaces an alias with its selector.
+    ///
+    /// Text that is not an alias is returned unchanged.
+    pub fn expand<'a>(&'a self, text: &'a str) -> &'a str {
+        self.devices
+            .get(text)
+            .map_or(text, |selector| &selector[..])
+    }
+
+    /// Parses a selector, replacing an alias with its selector.
+    pub fn selector(&self, text: &str) -> Result<DeviceSelector, SelectorError> {
+        self.expand(text).parse()
+    }
+}
+
+/// Describes a failure to select a device.
+#[derive(Debug)]
+pub enum SelectorError {
+    /// The selector has a prefix other than `id`, `name`, `interface`, or
+    /// `role`.
+    UnknownKind(String),
+    /// The role is not `console`, `multimedia`, or `communications`.
+    UnknownRole(String),
+    /// A regular expression is invalid.
+    InvalidPattern(regex::Error),
+    /// No device matches the selector.
+    NoMatch(String),
+    /// More than one device matches the selector.
+    MultipleMatches {
+        /// The selector
+        selector: String,
+        /// Descriptions of the matching devices
+        devices: Vec<String>,
+    },
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelectorError::UnknownKind(kind) => write!(
+                f,
+                "unknown selector {:?}; expected id, name, interface, or role",
+                kind
+            ),
+            SelectorError::UnknownRole(role) => {
+                let roles: Vec<_> = DeviceRole::ALL.iter().map(|role| role.name()).collect();
+                write!(
+                    f,
+                    "unknown role {:?}; expected one of {}",
+                    role,
+                    roles.join(", ")
+                )
+            }
+            SelectorError::InvalidPattern(error) => write!(f, "invalid pattern: {}", error),
+            SelectorError::NoMatch(selector) => {
+                write!(f, "no device matches {}", selector)
+            }
+            SelectorError::MultipleMatches { selector, devices } => write!(
+                f,
+                "{} matches more than one device: {}",
+                selector,
+                devices.join("; ")
+            ),
+        }
+    }
+}
+
+impl Error for SelectorError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            SelectorError::InvalidPattern(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(text: &str) -> Pattern {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn glob_matches_whole_text_ignoring_case() {
+        let pattern = pattern("Speakers*");
+        assert!(pattern.matches("Speakers"));
+        assert!(pattern.matches("speakers (Sound Blaster Z)"));
+        assert!(!pattern.matches("Headphones (Speakers)"));
+    }
+
+    #[test]
+    fn glob_question_mark_matches_one_character() {
+        let pattern = pattern("AE-?");
+        assert!(pattern.matches("AE-5"));
+        assert!(pattern.matches("ae-7"));
+        assert!(!pattern.matches("AE-5 Plus"));
+    }
+
+    #[test]
+    fn glob_escapes_regex_syntax() {
+        let pattern = pattern("Speakers (2.1)");
+        assert!(pattern.matches("Speakers (2.1)"));
+        assert!(!pattern.matches("Speakers (201)"));
+    }
+
+    #[test]
+    fn slashes_enclose_a_regex() {
+        let pattern = pattern("/AE-[57]/");
+        assert!(pattern.matches("Sound Blaster AE-5 Plus"));
+        assert!(!pattern.matches("sound blaster ae-5"));
+        assert!(matches!(
+            Pattern::from_str("/(/"),
+            Err(SelectorError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn pattern_displays_its_source() {
+        assert_eq!(pattern("/AE-[57]/").to_string(), "/AE-[57]/");
+        assert_eq!(pattern("Speakers*").to_string(), "Speakers*");
+    }
+
+    #[test]
+    fn text_without_colon_is_an_id() {
+        let id = "{0.0.0.00000000}.{8e2b1b5e-0000-0000-0000-000000000000}";
+        match DeviceSelector::from_str(id) {
+            Ok(DeviceSelector::Id(parsed)) => assert_eq!(parsed, id),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_prefixed_selectors() {
+        match DeviceSelector::from_str("id:{0.0.0.00000000}.{1}") {
+            Ok(DeviceSelector::Id(id)) => assert_eq!(id, "{0.0.0.00000000}.{1}"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match DeviceSelector::from_str("Name: \"Speakers*\"") {
+            Ok(DeviceSelector::Name(pattern)) => assert_eq!(pattern.to_string(), "Speakers*"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match DeviceSelector::from_str("interface:/AE-5/") {
+            Ok(DeviceSelector::Interface(pattern)) => assert!(pattern.matches("AE-5")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            DeviceSelector::from_str("role:Communications"),
+            Ok(DeviceSelector::Role(DeviceRole::Communications))
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_prefixes_and_roles() {
+        assert!(matches!(
+            DeviceSelector::from_str("nmae:Speakers"),
+            Err(SelectorError::UnknownKind(kind)) if kind == "nmae"
+        ));
+        assert!(matches!(
+            DeviceSelector::from_str("role:music"),
+            Err(SelectorError::UnknownRole(role)) if role == "music"
+        ));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "name:Speakers*",
+            "interface:/AE-5/",
+            "role:console",
+            "{0.0.0.00000000}.{1}",
+        ] {
+            assert_eq!(DeviceSelector::from_str(text).unwrap().to_string(), text);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::soundcore::{ParseParamValueError, SoundCoreParamValue};
+
+/// Describes how a value should be changed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting<T> {
+    /// Replace the current value.
+    Absolute(T),
+    /// Add to the current value.
+    ///
+    /// Negative amounts decrease the value.
+    Relative(T),
+}
+
+impl<T> From<T> for Setting<T> {
+    fn from(value: T) -> Self {
+        Setting::Absolute(value)
+    }
+}
+
+impl fmt::Display for Setting<SoundCoreParamValue> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Setting::Absolute(value) => value.fmt(f),
+            Setting::Relative(value) => match *value {
+                SoundCoreParamValue::Float(v) if v.is_sign_negative() => write!(f, "-={}", -v),
+                SoundCoreParamValue::I32(v) if v < 0 => write!(f, "-={}", -i64::from(v)),
+                _ => write!(f, "+={}", value),
+            },
+        }
+    }
+}
+
+impl FromStr for Setting<SoundCoreParamValue> {
+    type Err = ParseParamValueError;
+
+    /// Parses a parameter value.
+    ///
+    /// Values starting with `+=` or `-=` are relative adjustments. Any other
+    /// value, including a negative number such as `-3`, replaces the current
+    /// value.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        if let Some(amount) = text.strip_prefix("+=") {
+            Ok(Setting::Relative(SoundCoreParamValue::from_str(amount)?))
+        } else if let Some(amount) = text.strip_prefix("-=") {
+            negate(SoundCoreParamValue::from_str(amount)?)
+                .map(Setting::Relative)
+                .ok_or_else(|| ParseParamValueError::new(s))
+        } else {
+            Ok(Setting::Absolute(SoundCoreParamValue::from_str(text)?))
+        }
+    }
+}
+
+/// Negates a numeric value, or returns `None` if it has no negation.
+fn negate(value: SoundCoreParamValue) -> Option<SoundCoreParamValue> {
+    match value {
+        SoundCoreParamValue::Float(v) => Some(SoundCoreParamValue::Float(-v)),
+        SoundCoreParamValue::I32(v) => Some(match v.checked_neg() {
+            Some(v) => SoundCoreParamValue::I32(v),
+            None => SoundCoreParamValue::U32(v.unsigned_abs()),
+        }),
+        SoundCoreParamValue::U32(v) => i32::try_from(-i64::from(v))
+            .ok()
+            .map(SoundCoreParamValue::I32),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Setting<SoundCoreParamValue> {
+        Setting::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn plain_values_are_absolute() {
+        assert_eq!(parse("5"), Setting::Absolute(SoundCoreParamValue::I32(5)));
+        assert_eq!(
+            parse("0.5"),
+            Setting::Absolute(SoundCoreParamValue::Float(0.5))
+        );
+        assert_eq!(
+            parse("true"),
+            Setting::Absolute(SoundCoreParamValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn signed_values_are_absolute() {
+        assert_eq!(parse("-3"), Setting::Absolute(SoundCoreParamValue::I32(-3)));
+        assert_eq!(
+            parse("-5i"),
+            Setting::Absolute(SoundCoreParamValue::I32(-5))
+        );
+        assert_eq!(parse("+3"), Setting::Absolute(SoundCoreParamValue::I32(3)));
+        assert_eq!(
+            parse("-0.1"),
+            Setting::Absolute(SoundCoreParamValue::Float(-0.1))
+        );
+    }
+
+    #[test]
+    fn increments_are_relative() {
+        assert_eq!(parse("+=3"), Setting::Relative(SoundCoreParamValue::I32(3)));
+        assert_eq!(
+            parse("+=0.1"),
+            Setting::Relative(SoundCoreParamValue::Float(0.1))
+        );
+        assert_eq!(
+            parse(" += 2 "),
+            Setting::Relative(SoundCoreParamValue::I32(2))
+        );
+    }
+
+    #[test]
+    fn decrements_are_negated() {
+        assert_eq!(
+            parse("-=3"),
+            Setting::Relative(SoundCoreParamValue::I32(-3))
+        );
+        assert_eq!(
+            parse("-=0.1"),
+            Setting::Relative(SoundCoreParamValue::Float(-0.1))
+        );
+        assert_eq!(
+            parse("-=5u"),
+            Setting::Relative(SoundCoreParamValue::I32(-5))
+        );
+    }
+
+    #[test]
+    fn decrementing_a_negative_amount_increments() {
+        assert_eq!(
+            parse("-=-5"),
+            Setting::Relative(SoundCoreParamValue::I32(5))
+        );
+        assert_eq!(
+            parse("-=-0.5"),
+            Setting::Relative(SoundCoreParamValue::Float(0.5))
+        );
+        assert_eq!(
+            parse("-=-2147483648"),
+            Setting::Relative(SoundCoreParamValue::U32(2_147_483_648))
+        );
+    }
+
+    #[test]
+    fn rejects_decrements_without_a_negation() {
+        assert!(Setting::<SoundCoreParamValue>::from_str("-=true").is_err());
+        assert!(Setting::<SoundCoreParamValue>::from_str("-=4000000000").is_err());
+        assert!(Setting::<SoundCoreParamValue>::from_str("-=hex:00").is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "-3",
+            "5",
+            "+=3",
+            "-=3",
+            "+=0.5",
+            "-=0.5",
+            "-=-5",
+            "+=-5",
+            "-=2147483648",
+        ] {
+            let setting = parse(text);
+            assert_eq!(parse(&setting.to_string()), setting, "{}", text);
+        }
+        assert_eq!(parse("-=3").to_string(), "-=3");
+        assert_eq!(parse("+=3").to_string(), "+=3");
+    }
+}
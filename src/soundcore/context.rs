@@ -0,0 +1,30 @@
+use std::str;
+
+use crate::ctsndcr::ContextInfo;
+
+/// Represents a context a device may operate in.
+///
+/// Each context has its own set of features and parameter values.
+#[derive(Clone, Debug)]
+pub struct SoundCoreContext {
+    /// A numeric ID of the context
+    pub id: u32,
+    /// A description of the context
+    pub description: String,
+}
+
+impl SoundCoreContext {
+    pub(crate) fn new(info: &ContextInfo) -> Self {
+        let description_length = info
+            .description
+            .iter()
+            .position(|i| *i == 0)
+            .unwrap_or(info.description.len());
+        Self {
+            id: info.context_id,
+            description: str::from_utf8(&info.description[0..description_length])
+                .unwrap()
+                .to_owned(),
+        }
+    }
+}
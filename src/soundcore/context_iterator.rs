@@ -0,0 +1,42 @@
+use std::mem::MaybeUninit;
+
+use tracing::trace_span;
+use windows::Win32::Foundation::E_FAIL;
+
+use crate::com::ComObject;
+use crate::ctsndcr::ISoundCore;
+
+use super::SoundCoreContext;
+
+/// Iterates over the contexts of a device.
+pub struct SoundCoreContextIterator {
+    target: ComObject<ISoundCore>,
+    index: u32,
+}
+
+impl SoundCoreContextIterator {
+    pub(crate) fn new(target: ComObject<ISoundCore>) -> Self {
+        Self { target, index: 0 }
+    }
+}
+
+impl Iterator for SoundCoreContextIterator {
+    type Item = windows::core::Result<SoundCoreContext>;
+
+    fn next(&mut self) -> Option<windows::core::Result<SoundCoreContext>> {
+        unsafe {
+            let span = trace_span!("Fetching context", index = self.index);
+            let _span = span.enter();
+            let mut info = MaybeUninit::uninit();
+            let info = match self.target.EnumContexts(self.index, info.as_mut_ptr()).ok() {
+                Ok(()) => info.assume_init(),
+                // FAIL used to mark end of collection
+                Err(error) if error.code() == E_FAIL => return None,
+                Err(error) => return Some(Err(error)),
+            };
+            span.record("info", tracing::field::debug(&info));
+            self.index += 1;
+            Some(Ok(SoundCoreContext::new(&info)))
+        }
+    }
+}
@@ -1,15 +1,17 @@
 use std::ffi::OsStr;
+use std::mem::MaybeUninit;
 use std::os::windows::ffi::OsStrExt;
 
 use tracing::instrument;
-use windows::core::{Interface, GUID};
+use windows::core::GUID;
 use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
 
 use crate::com::{ComObject, ComScope};
-use crate::ctsndcr::{HardwareInfo, IEventNotify, ISoundCore};
+use crate::ctsndcr::{HardwareInfo, ISoundCore};
 
-use super::event::{SoundCoreEventIterator, SoundCoreEvents};
-use super::{SoundCoreError, SoundCoreFeatureIterator};
+use super::event::{EventMask, SoundCoreEventIterator, SoundCoreEvents};
+use super::hub::{SoundCoreEventHub, SubscribeOptions};
+use super::{SoundCoreContext, SoundCoreContextIterator, SoundCoreError, SoundCoreFeatureIterator};
 
 /// Provides control of Creative SoundBlaster features.
 ///
@@ -17,6 +19,7 @@ use super::{SoundCoreError, SoundCoreFeatureIterator};
 #[derive(Debug)]
 pub struct SoundCore {
     sound_core: ComObject<ISoundCore>,
+    device_id: String,
 }
 
 impl SoundCore {
@@ -39,6 +42,7 @@ impl SoundCore {
             let sc: ISoundCore = CoCreateInstance(clsid, None, CLSCTX_ALL)?;
             Ok(SoundCore {
                 sound_core: ComObject::take(sc),
+                device_id: String::new(),
             })
         }
     }
@@ -52,7 +56,43 @@ impl SoundCore {
             info_type: 0,
             info: buffer,
         };
-        unsafe { self.sound_core.BindHardware(&info).ok() }
+        unsafe { self.sound_core.BindHardware(&info).ok()? };
+        self.device_id = id.to_owned();
+        Ok(())
+    }
+    /// Returns an iterator over the contexts of a device.
+    pub fn contexts(&self) -> SoundCoreContextIterator {
+        SoundCoreContextIterator::new(self.sound_core.clone())
+    }
+    /// Gets information about a context.
+    pub fn context_info(&self, context: u32) -> windows::core::Result<SoundCoreContext> {
+        unsafe {
+            let mut info = MaybeUninit::uninit();
+            self.sound_core
+                .GetContextInfo(context, info.as_mut_ptr())
+                .ok()?;
+            Ok(SoundCoreContext::new(&info.assume_init()))
+        }
+    }
+    /// Gets the ID of the context the device is currently operating in.
+    pub fn current_context(&self) -> windows::core::Result<u32> {
+        unsafe {
+            let mut context = 0;
+            self.sound_core.GetContext(&mut context).ok()?;
+            Ok(context)
+        }
+    }
+    /// Switches the device to another context.
+    ///
+    /// `restore_state` asks the device to restore the parameter values it
+    /// last used in that context.
+    #[instrument(level = "debug")]
+    pub fn set_context(&self, context: u32, restore_state: bool) -> windows::core::Result<()> {
+        unsafe {
+            self.sound_core
+                .SetContext(context, u32::from(restore_state))
+                .ok()
+        }
     }
     /// Returns an iterator over the features exposed by a device.
     pub fn features(&self, context: u32) -> SoundCoreFeatureIterator {
@@ -67,18 +107,31 @@ impl SoundCore {
     /// Events are only received during a call to `next`.
     /// Events are likely dropped if `next` is called infrequently.
     ///
-    /// Event iterators with overlapping lifetimes may produce unexpected results
-    /// because the SoundCore API for events does not seem to support registering
-    /// multiple event handlers and then unregistering only one of them. Probably
-    /// this is okay if done with multiple `SoundCore` instances.
+    /// The SoundCore API only supports one event handler per device, so
+    /// event iterators for the same device on the same thread share a
+    /// [`SoundCoreEventHub`](struct.SoundCoreEventHub.html).
     pub fn events(&self) -> windows::core::Result<SoundCoreEventIterator> {
-        Ok(SoundCoreEventIterator::new(self.event_stream()?))
+        self.events_with_mask(EventMask::ALL)
+    }
+    /// Returns an iterator over the events selected by `mask`.
+    ///
+    /// See [`events`](#method.events).
+    pub fn events_with_mask(
+        &self,
+        mask: EventMask,
+    ) -> windows::core::Result<SoundCoreEventIterator> {
+        Ok(SoundCoreEventIterator::new(self.event_stream(mask)?))
     }
 
-    pub(crate) fn event_stream(&self) -> windows::core::Result<SoundCoreEvents> {
-        unsafe {
-            let event_notify: IEventNotify = self.sound_core.cast()?;
-            SoundCoreEvents::new(ComObject::take(event_notify), self.sound_core.clone())
-        }
+    /// Gets the event hub shared by the event iterators of this device.
+    pub fn event_hub(&self) -> windows::core::Result<SoundCoreEventHub> {
+        SoundCoreEventHub::for_core(&self.device_id, &self.sound_core)
+    }
+
+    pub(crate) fn event_stream(&self, mask: EventMask) -> windows::core::Result<SoundCoreEvents> {
+        self.event_hub()?.event_stream(&SubscribeOptions {
+            mask,
+            ..SubscribeOptions::default()
+        })
     }
 }
@@ -1,43 +1,160 @@
 use futures::channel::mpsc;
 use futures::Stream;
+use tracing::{debug, warn};
+use windows::Win32::Foundation::HANDLE;
 
 use std::clone::Clone;
+use std::collections::HashMap;
 use std::mem::MaybeUninit;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use crate::com::event::ComEventIterator;
+use crate::com::event::{CancelHandle, ComEventIterator};
 use crate::com::ComObject;
-use crate::ctsndcr::{Callback, EventInfo, IEventNotify, ISoundCore, Param};
+use crate::ctsndcr::{EventInfo, ISoundCore, Param};
+use crate::debounce::Coalesce;
 
-use super::{SoundCoreFeature, SoundCoreParameter};
+use super::hub::Subscription;
+use super::{SoundCoreContext, SoundCoreFeature, SoundCoreParamValue, SoundCoreParameter};
 
 pub(crate) struct SoundCoreEvents {
-    event_notify: ComObject<IEventNotify>,
-    events: mpsc::UnboundedReceiver<EventInfo>,
+    events: mpsc::Receiver<EventInfo>,
     core: ComObject<ISoundCore>,
+    context: u32,
+    /// The last known value of each parameter, by feature and parameter id
+    values: HashMap<(u32, u32), SoundCoreParamValue>,
+    // dropped last, so the hub can unregister once nothing can receive
+    _subscription: Subscription,
 }
 
 impl SoundCoreEvents {
-    pub fn new(
-        event_notify: ComObject<IEventNotify>,
-        core: ComObject<ISoundCore>,
-    ) -> windows::core::Result<Self> {
-        let (tx, rx) = mpsc::unbounded();
+    pub fn new(subscription: Subscription, events: mpsc::Receiver<EventInfo>) -> Self {
+        let core = subscription.core().clone();
+        // events do not say which context they belong to
+        let mut context = 0;
+        if let Err(error) = unsafe { core.GetContext(&mut context) }.ok() {
+            debug!(error = %error, "Could not get current context; assuming 0");
+        }
+        Self {
+            events,
+            core,
+            context,
+            values: HashMap::new(),
+            _subscription: subscription,
+        }
+    }
 
+    fn feature(&self, id: u32) -> windows::core::Result<SoundCoreFeature> {
         unsafe {
-            let callback = Callback::new(tx);
+            let mut feature = MaybeUninit::uninit();
+            self.core
+                .GetFeatureInfo(self.context, id, feature.as_mut_ptr())
+                .ok()?;
+            Ok(SoundCoreFeature::new(
+                self.core.clone(),
+                self.context,
+                &feature.assume_init(),
+            ))
+        }
+    }
 
-            (*event_notify)
-                .RegisterEventCallback(0xff, callback.into())
+    fn parameter(
+        &self,
+        feature: &SoundCoreFeature,
+        id: u32,
+    ) -> windows::core::Result<SoundCoreParameter> {
+        unsafe {
+            let mut param = MaybeUninit::uninit();
+            self.core
+                .GetParamInfo(
+                    Param {
+                        param: id,
+                        feature: feature.id,
+                        context: self.context,
+                    },
+                    param.as_mut_ptr(),
+                )
                 .ok()?;
+            Ok(SoundCoreParameter::new(
+                self.core.clone(),
+                feature.description.clone(),
+                &param.assume_init(),
+            ))
         }
+    }
 
-        Ok(Self {
-            event_notify,
-            events: rx,
-            core,
-        })
+    /// Gets the id of the context the events are translated in.
+    pub fn context(&self) -> u32 {
+        self.context
+    }
+
+    /// Remembers the current value of a parameter without reporting it, so
+    /// that only later changes are reported.
+    pub fn seed(&mut self, feature_id: u32, param_id: u32) -> windows::core::Result<()> {
+        let feature = self.feature(feature_id)?;
+        let value = self.parameter(&feature, param_id)?.get()?;
+        self.values.insert((feature_id, param_id), value);
+        Ok(())
+    }
+
+    /// Reads a parameter that may have changed, after a notification or
+    /// while polling.
+    ///
+    /// Returns `None` if the value is the same as when it was last seen, so
+    /// a change noticed both ways is only reported once.
+    pub fn param_change(
+        &mut self,
+        feature_id: u32,
+        param_id: u32,
+    ) -> windows::core::Result<Option<SoundCoreEvent>> {
+        let feature = self.feature(feature_id)?;
+        let parameter = self.parameter(&feature, param_id)?;
+        // read the value now, before it can change again
+        let new = parameter.get()?;
+        let old = self.values.insert((feature_id, param_id), new.clone());
+        if old.as_ref() == Some(&new) {
+            return Ok(None);
+        }
+        Ok(Some(SoundCoreEvent::ParamChange {
+            feature,
+            parameter,
+            old,
+            new,
+        }))
+    }
+
+    /// Translates a notification of a type that is not understood.
+    ///
+    /// The meaning of these events is not documented, so they are recognized
+    /// by their effects: the device switching to another context, or the
+    /// event referring to a feature and parameter that exist.
+    fn other_event(&mut self, e: EventInfo) -> SoundCoreEvent {
+        let mut context = 0;
+        if unsafe { self.core.GetContext(&mut context) }.is_ok() && context != self.context {
+            let mut info = MaybeUninit::uninit();
+            if unsafe { self.core.GetContextInfo(context, info.as_mut_ptr()) }.is_ok() {
+                debug!(from = self.context, to = context, "Context changed");
+                self.context = context;
+                // parameters of the new context have their own values
+                self.values.clear();
+                return SoundCoreEvent::ContextChange {
+                    info: e,
+                    context: SoundCoreContext::new(unsafe { &info.assume_init() }),
+                };
+            }
+        }
+        match self.feature(e.data_or_feature_id) {
+            Ok(feature) => {
+                let parameter = self.parameter(&feature, e.param_id).ok();
+                SoundCoreEvent::FeatureEvent {
+                    info: e,
+                    feature,
+                    parameter,
+                }
+            }
+            Err(_) => SoundCoreEvent::Unknown(e),
+        }
     }
 }
 
@@ -45,61 +162,57 @@ impl Stream for SoundCoreEvents {
     type Item = windows::core::Result<SoundCoreEvent>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        match Pin::new(&mut self.events).poll_next(cx) {
-            Poll::Ready(Some(e)) => Poll::Ready(Some(match e.event {
-                2 => unsafe {
-                    let mut feature = MaybeUninit::uninit();
-                    match self
-                        .core
-                        .GetFeatureInfo(0, e.data_or_feature_id, feature.as_mut_ptr())
-                        .ok()
-                    {
-                        Ok(()) => {
-                            let feature =
-                                SoundCoreFeature::new(self.core.clone(), 0, &feature.assume_init());
-                            let mut param = MaybeUninit::uninit();
-                            match self
-                                .core
-                                .GetParamInfo(
-                                    Param {
-                                        param: e.param_id,
-                                        feature: e.data_or_feature_id,
-                                        context: 0,
-                                    },
-                                    param.as_mut_ptr(),
-                                )
-                                .ok()
-                            {
-                                Ok(()) => {
-                                    let param = SoundCoreParameter::new(
-                                        self.core.clone(),
-                                        feature.description.clone(),
-                                        &param.assume_init(),
-                                    );
-                                    Ok(SoundCoreEvent::ParamChange {
-                                        feature,
-                                        parameter: param,
-                                    })
-                                }
-                                Err(error) => Err(error),
+        loop {
+            match Pin::new(&mut self.events).poll_next(cx) {
+                Poll::Ready(Some(e)) => {
+                    debug!(
+                        event = e.event,
+                        data = e.data_or_feature_id,
+                        param = e.param_id,
+                        "Received SoundCore event",
+                    );
+                    return Poll::Ready(Some(match e.event {
+                        2 => match self.param_change(e.data_or_feature_id, e.param_id) {
+                            Ok(Some(event)) => Ok(event),
+                            // nothing changed, so wait for the next event
+                            Ok(None) => continue,
+                            // one unreadable parameter should not end the events
+                            Err(error) => {
+                                warn!(
+                                    feature = e.data_or_feature_id,
+                                    param = e.param_id,
+                                    error = %error,
+                                    "Could not read changed parameter; skipping event",
+                                );
+                                continue;
                             }
-                        }
-                        Err(error) => Err(error),
-                    }
-                },
-                _ => Ok(SoundCoreEvent::Unknown(e)),
-            })),
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
+                        },
+                        _ => Ok(self.other_event(e)),
+                    }));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
         }
     }
 }
 
-impl Drop for SoundCoreEvents {
-    fn drop(&mut self) {
-        unsafe {
-            self.event_notify.UnregisterEventCallback().ok().unwrap();
-        }
+/// Selects the SoundCore events to register for.
+///
+/// The meaning of the individual bits is not documented. Creative's own
+/// software registers with `0xff`, which is the default; other masks are
+/// mostly useful for finding out what the bits do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventMask(pub u32);
+
+impl EventMask {
+    /// Registers for every known event.
+    pub const ALL: EventMask = EventMask(0xff);
+}
+
+impl Default for EventMask {
+    fn default() -> Self {
+        EventMask::ALL
     }
 }
 
@@ -108,7 +221,9 @@ impl Drop for SoundCoreEvents {
 /// This allows a program to be notified of events such as switching
 /// between headphones and speakers.
 ///
-/// This iterator will block until the next event is available.
+/// This iterator will block until the next event is available. Use
+/// [`try_next`](#method.try_next) or [`next_timeout`](#method.next_timeout)
+/// to wait for a limited time.
 pub struct SoundCoreEventIterator {
     inner: ComEventIterator<SoundCoreEvents>,
 }
@@ -119,6 +234,40 @@ impl SoundCoreEventIterator {
             inner: ComEventIterator::new(stream),
         }
     }
+
+    /// Gets the next event if one is available, without blocking.
+    ///
+    /// Returns `Poll::Pending` if there is no event yet, and
+    /// `Poll::Ready(None)` if the iterator has ended or was cancelled.
+    pub fn try_next(&mut self) -> Poll<Option<windows::core::Result<SoundCoreEvent>>> {
+        self.inner.try_next()
+    }
+
+    /// Waits up to `timeout` for the next event.
+    ///
+    /// Returns `Poll::Pending` if the timeout passed, and
+    /// `Poll::Ready(None)` if the iterator has ended or was cancelled.
+    pub fn next_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Poll<Option<windows::core::Result<SoundCoreEvent>>> {
+        self.inner.next_timeout(timeout)
+    }
+
+    /// Gets a handle that stops the iterator from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.inner.cancel_handle()
+    }
+
+    /// Gets an event handle that is signaled when an event may be available.
+    ///
+    /// This allows waiting for events together with other handles, such as
+    /// in `MsgWaitForMultipleObjects`, and then calling
+    /// [`try_next`](#method.try_next). The handle is an auto-reset event,
+    /// and is closed when the iterator is dropped.
+    pub fn event_handle(&self) -> HANDLE {
+        self.inner.event_handle()
+    }
 }
 
 impl Iterator for SoundCoreEventIterator {
@@ -131,16 +280,81 @@ impl Iterator for SoundCoreEventIterator {
 
 /// Describes an event produced through the SoundCore API.
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum SoundCoreEvent {
     /// An event occurred that could not be translated.
     ///
     /// The original event information is included unmodified.
     Unknown(EventInfo),
+    /// The device switched to another context
+    ///
+    /// Parameter changes are reported for the new context from now on.
+    ContextChange {
+        /// The original event information
+        info: EventInfo,
+        /// The context the device is now operating in
+        context: SoundCoreContext,
+    },
+    /// An event of an unrecognized type referred to a feature
+    FeatureEvent {
+        /// The original event information
+        info: EventInfo,
+        /// The feature the event referred to
+        feature: SoundCoreFeature,
+        /// The parameter the event referred to, if it exists
+        parameter: Option<SoundCoreParameter>,
+    },
     /// A parameter value has changed
+    ///
+    /// Notifications that do not change the value since it was last seen
+    /// are not reported.
     ParamChange {
         /// The feature that changed
         feature: SoundCoreFeature,
         /// The parameter that changed
         parameter: SoundCoreParameter,
+        /// The value before the change, if it has been seen before
+        old: Option<SoundCoreParamValue>,
+        /// The value when the change was reported
+        new: SoundCoreParamValue,
     },
 }
+
+impl Coalesce for SoundCoreEvent {
+    /// The feature and parameter ids
+    type Key = (u32, u32);
+
+    fn coalesce_key(&self) -> Option<Self::Key> {
+        match self {
+            SoundCoreEvent::ParamChange {
+                feature, parameter, ..
+            } => Some((feature.id, parameter.id)),
+            _ => None,
+        }
+    }
+
+    fn coalesce(self, later: Self) -> Option<Self> {
+        match (self, later) {
+            (
+                SoundCoreEvent::ParamChange { old, .. },
+                SoundCoreEvent::ParamChange {
+                    feature,
+                    parameter,
+                    new,
+                    ..
+                },
+            ) => {
+                if old.as_ref() == Some(&new) {
+                    return None;
+                }
+                Some(SoundCoreEvent::ParamChange {
+                    feature,
+                    parameter,
+                    old,
+                    new,
+                })
+            }
+            (_, later) => Some(later),
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Shares one SoundCore event registration between many subscribers.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Mutex};
+
+use futures::channel::mpsc;
+use tracing::{debug, warn};
+use windows::core::{Interface, GUID};
+
+use crate::com::ComObject;
+use crate::ctsndcr::{Callback, EventInfo, IEventNotify, ISoundCore};
+
+use super::event::{EventMask, SoundCoreEventIterator, SoundCoreEvents};
+use super::{SoundCore, SoundCoreError};
+
+thread_local! {
+    /// The hub of each device on this thread, by device id
+    static HUBS: RefCell<HashMap<String, Weak<Hub>>> = RefCell::new(HashMap::new());
+}
+
+/// Decides which raw events a subscriber receives.
+pub type EventFilter = Arc<dyn Fn(&EventInfo) -> bool + Send + Sync>;
+
+/// Options for a subscription to a [`SoundCoreEventHub`](struct.SoundCoreEventHub.html).
+#[derive(Clone)]
+pub struct SubscribeOptions {
+    /// The events the device should report.
+    ///
+    /// The hub registers for the union of the masks of its subscribers, so
+    /// a subscriber may also receive events outside of its own mask.
+    pub mask: EventMask,
+    /// The number of events queued for the subscriber before further events
+    /// are dropped.
+    pub capacity: usize,
+    /// Receives only the events accepted by this filter, if any.
+    pub filter: Option<EventFilter>,
+}
+
+impl Default for SubscribeOptions {
+    fn default() -> Self {
+        SubscribeOptions {
+            mask: EventMask::default(),
+            capacity: 64,
+            filter: None,
+        }
+    }
+}
+
+impl fmt::Debug for SubscribeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SubscribeOptions")
+            .field("mask", &self.mask)
+            .field("capacity", &self.capacity)
+            .field("filter", &self.filter.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+struct Subscriber {
+    id: u64,
+    filter: Option<EventFilter>,
+    sender: mpsc::Sender<EventInfo>,
+}
+
+/// Delivers an event to every subscriber that accepts it.
+///
+/// This runs in the SoundCore callback, so it must not block.
+fn broadcast(subscribers: &Mutex<Vec<Subscriber>>, event: EventInfo) {
+    for subscriber in subscribers.lock().unwrap().iter_mut() {
+        if let Some(ref filter) = subscriber.filter {
+            if !filter(&event) {
+                continue;
+            }
+        }
+        if let Err(error) = subscriber.sender.try_send(event) {
+            // a disconnected subscriber is about to unsubscribe
+            if error.is_full() {
+                warn!(
+                    subscriber = subscriber.id,
+                    event = event.event,
+                    "Subscriber is not keeping up; dropping event",
+                );
+            }
+        }
+    }
+}
+
+struct Hub {
+    device_id: String,
+    core: ComObject<ISoundCore>,
+    event_notify: ComObject<IEventNotify>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    /// The mask of the current registration, if registered
+    mask: Cell<Option<EventMask>>,
+    next_id: Cell<u64>,
+}
+
+impl Hub {
+    /// Makes sure the callback is registered for at least `mask`.
+    fn register(&self, mask: EventMask) -> windows::core::Result<()> {
+        let mask = match self.mask.get() {
+            Some(current) if current.0 | mask.0 == current.0 => return Ok(()),
+            Some(current) => {
+                // the API only supports one callback, so replace it
+                unsafe { self.event_notify.UnregisterEventCallback().ok()? };
+                self.mask.set(None);
+                EventMask(current.0 | mask.0)
+            }
+            None => mask,
+        };
+        let subscribers = self.subscribers.clone();
+        let callback = Callback::new(Arc::new(move |event| broadcast(&subscribers, event)));
+        unsafe {
+            self.event_notify
+                .RegisterEventCallback(mask.0, callback.into())
+                .ok()?;
+        }
+        debug!(device = %self.device_id, mask = mask.0, "Registered SoundCore callback");
+        self.mask.set(Some(mask));
+        Ok(())
+    }
+
+    fn unregister(&self) {
+        if self.mask.take().is_some() {
+            if let Err(error) = unsafe { self.event_notify.UnregisterEventCallback().ok() } {
+                warn!(device = %self.device_id, error = %error, "Could not unregister SoundCore callback");
+            } else {
+                debug!(device = %self.device_id, "Unregistered SoundCore callback");
+            }
+        }
+    }
+
+    fn unsubscribe(&self, id: u64) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| subscriber.id != id);
+        let empty = subscribers.is_empty();
+        // the callback takes the lock, so release it before unregistering
+        drop(subscribers);
+        if empty {
+            self.unregister();
+        }
+    }
+}
+
+impl Drop for Hub {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
+
+/// Keeps a subscriber registered with its hub.
+pub(crate) struct Subscription {
+    hub: Rc<Hub>,
+    id: u64,
+}
+
+impl Subscription {
+    pub fn core(&self) -> &ComObject<ISoundCore> {
+        &self.hub.core
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.hub.unsubscribe(self.id);
+    }
+}
+
+/// Shares the SoundCore events of a device between any number of subscribers.
+///
+/// The SoundCore API only supports a single event callback per device, so
+/// the hub registers one callback and broadcasts its events to each
+/// subscriber's queue. The callback is registered when the first subscriber
+/// subscribes and unregistered when the last one is dropped.
+///
+/// Hubs are shared per device on each thread: every hub for the same device
+/// on the same thread uses the same registration. Events are only received
+/// while one of the subscribers is waiting for events.
+#[derive(Clone)]
+pub struct SoundCoreEventHub {
+    hub: Rc<Hub>,
+}
+
+impl SoundCoreEventHub {
+    /// Gets the hub of a device, creating it if this thread does not have one.
+    ///
+    /// `clsid` and `device_id` are the same as for
+    /// [`SoundCore::for_device`](struct.SoundCore.html#method.for_device).
+    pub fn for_device(clsid: &GUID, device_id: &str) -> Result<Self, SoundCoreError> {
+        match Self::existing(device_id) {
+            Some(hub) => Ok(hub),
+            None => Ok(SoundCore::for_device(clsid, device_id)?.event_hub()?),
+        }
+    }
+
+    pub(crate) fn for_core(
+        device_id: &str,
+        core: &ComObject<ISoundCore>,
+    ) -> windows::core::Result<Self> {
+        if let Some(hub) = Self::existing(device_id) {
+            return Ok(hub);
+        }
+        let event_notify: IEventNotify = core.cast()?;
+        let hub = Rc::new(Hub {
+            device_id: device_id.to_owned(),
+            core: core.clone(),
+            event_notify: unsafe { ComObject::take(event_notify) },
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            mask: Cell::new(None),
+            next_id: Cell::new(0),
+        });
+        HUBS.with(|hubs| {
+            let mut hubs = hubs.borrow_mut();
+            hubs.retain(|_, hub| hub.strong_count() > 0);
+            hubs.insert(device_id.to_owned(), Rc::downgrade(&hub));
+        });
+        Ok(Self { hub })
+    }
+
+    fn existing(device_id: &str) -> Option<Self> {
+        HUBS.with(|hubs| hubs.borrow().get(device_id).and_then(Weak::upgrade))
+            .map(|hub| Self { hub })
+    }
+
+    /// Gets the id of the device.
+    pub fn device_id(&self) -> &str {
+        &self.hub.device_id
+    }
+
+    /// Gets the number of current subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.hub.subscribers.lock().unwrap().len()
+    }
+
+    /// Subscribes to every event of the device.
+    ///
+    /// The returned iterator blocks in the same way as
+    /// [`SoundCore::events`](struct.SoundCore.html#method.events).
+    pub fn subscribe(&self) -> windows::core::Result<SoundCoreEventIterator> {
+        self.subscribe_with(&SubscribeOptions::default())
+    }
+
+    /// Subscribes to the events of the device selected by `options`.
+    pub fn subscribe_with(
+        &self,
+        options: &SubscribeOptions,
+    ) -> windows::core::Result<SoundCoreEventIterator> {
+        Ok(SoundCoreEventIterator::new(self.event_stream(options)?))
+    }
+
+    pub(crate) fn event_stream(
+        &self,
+        options: &SubscribeOptions,
+    ) -> windows::core::Result<SoundCoreEvents> {
+        let (tx, rx) = mpsc::channel(options.capacity);
+        let id = self.hub.next_id.get();
+        self.hub.next_id.set(id + 1);
+        self.hub.subscribers.lock().unwrap().push(Subscriber {
+            id,
+            filter: options.filter.clone(),
+            sender: tx,
+        });
+        // unsubscribes again if registering fails
+        let subscription = Subscription {
+            hub: self.hub.clone(),
+            id,
+        };
+        self.hub.register(options.mask)?;
+        Ok(SoundCoreEvents::new(subscription, rx))
+    }
+}
+
+impl fmt::Debug for SoundCoreEventHub {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SoundCoreEventHub")
+            .field("device_id", &self.hub.device_id)
+            .field("mask", &self.hub.mask.get())
+            .field("subscribers", &self.subscriber_count())
+            .finish()
+    }
+}
@@ -6,20 +6,26 @@
 //!    to get a `SoundCore` for that device.
 
 mod consts;
+mod context;
+mod context_iterator;
 mod core;
 mod error;
 mod event;
 mod feature;
 mod feature_iterator;
+mod hub;
 mod parameter;
 mod parameter_iterator;
 
 pub use self::consts::*;
+pub use self::context::SoundCoreContext;
+pub use self::context_iterator::SoundCoreContextIterator;
 pub use self::core::SoundCore;
 pub use self::error::SoundCoreError;
 pub(crate) use self::event::SoundCoreEvents;
-pub use self::event::{SoundCoreEvent, SoundCoreEventIterator};
+pub use self::event::{EventMask, SoundCoreEvent, SoundCoreEventIterator};
 pub use self::feature::SoundCoreFeature;
 pub use self::feature_iterator::SoundCoreFeatureIterator;
-pub use self::parameter::{SoundCoreParamValue, SoundCoreParameter};
+pub use self::hub::{EventFilter, SoundCoreEventHub, SubscribeOptions};
+pub use self::parameter::{ParseParamValueError, SoundCoreParamValue, SoundCoreParameter};
 pub use self::parameter_iterator::SoundCoreParameterIterator;
@@ -1,6 +1,8 @@
+use std::error::Error;
+use std::fmt;
 use std::mem;
 use std::mem::MaybeUninit;
-use std::str;
+use std::str::{self, FromStr};
 
 use tracing::{info, trace, trace_span};
 use windows::Win32::Foundation::E_ACCESSDENIED;
@@ -9,7 +11,7 @@ use crate::com::ComObject;
 use crate::ctsndcr::{ISoundCore, Param, ParamInfo, ParamValue};
 
 /// Captures the value of a parameter.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SoundCoreParamValue {
     /// A floating point value
     Float(f32),
@@ -19,10 +21,111 @@ pub enum SoundCoreParamValue {
     U32(u32),
     /// A signed integer value
     I32(i32),
+    /// A variable-size value, such as an equalizer curve
+    Bytes(Vec<u8>),
     /// No value
     None,
 }
 
+impl fmt::Display for SoundCoreParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SoundCoreParamValue::Float(v) => v.fmt(f),
+            SoundCoreParamValue::Bool(v) => v.fmt(f),
+            SoundCoreParamValue::U32(v) => v.fmt(f),
+            SoundCoreParamValue::I32(v) => v.fmt(f),
+            SoundCoreParamValue::Bytes(ref v) => write!(f, "base64:{}", base64::encode(v)),
+            SoundCoreParamValue::None => f.write_str("<none>"),
+        }
+    }
+}
+
+impl FromStr for SoundCoreParamValue {
+    type Err = ParseParamValueError;
+
+    /// Parses a parameter value.
+    ///
+    /// Integers are parsed as `I32` unless they are too large, in which case
+    /// they are parsed as `U32`. Numbers with a decimal point or exponent are
+    /// parsed as `Float`.
+    ///
+    /// The type can be given explicitly with a suffix: `5u` is `U32`, `5i` is
+    /// `I32`, and `5f` is `Float`.
+    ///
+    /// `Bytes` are written as `base64:` or `hex:` followed by the data, e.g.
+    /// `base64:AAEC` or `hex:000102`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let value = if let Some(data) = text.strip_prefix("base64:") {
+            base64::decode(data.trim())
+                .ok()
+                .map(SoundCoreParamValue::Bytes)
+        } else if let Some(data) = text.strip_prefix("hex:") {
+            decode_hex(data).map(SoundCoreParamValue::Bytes)
+        } else if let Ok(b) = bool::from_str(text) {
+            Some(SoundCoreParamValue::Bool(b))
+        } else if let Ok(i) = i32::from_str(text) {
+            Some(SoundCoreParamValue::I32(i))
+        } else if let Ok(u) = u32::from_str(text) {
+            Some(SoundCoreParamValue::U32(u))
+        } else if let Some(f) = f32::from_str(text).ok().filter(|f| f.is_finite()) {
+            Some(SoundCoreParamValue::Float(f))
+        } else if let Some(number) = text.strip_suffix('u') {
+            u32::from_str(number).ok().map(SoundCoreParamValue::U32)
+        } else if let Some(number) = text.strip_suffix('i') {
+            i32::from_str(number).ok().map(SoundCoreParamValue::I32)
+        } else if let Some(number) = text.strip_suffix('f') {
+            f32::from_str(number)
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(SoundCoreParamValue::Float)
+        } else {
+            None
+        };
+        value.ok_or_else(|| ParseParamValueError {
+            input: s.to_owned(),
+        })
+    }
+}
+
+/// Decodes hexadecimal digits, ignoring whitespace between bytes.
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Describes a failure to parse a parameter value.
+#[derive(Debug)]
+pub struct ParseParamValueError {
+    input: String,
+}
+
+impl fmt::Display for ParseParamValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid parameter value {:?}; expected true, false, a number such as 5, 5u, 5i, or 5.0, or data such as base64:AAEC or hex:000102",
+            self.input
+        )
+    }
+}
+
+impl ParseParamValueError {
+    pub(crate) fn new(input: &str) -> Self {
+        ParseParamValueError {
+            input: input.to_owned(),
+        }
+    }
+}
+
+impl Error for ParseParamValueError {}
+
 /// Represents a parameter of a feature.
 #[derive(Debug)]
 pub struct SoundCoreParameter {
@@ -83,10 +186,11 @@ impl SoundCoreParameter {
     ///
     /// May return `SoundCoreParamValue::None` when getting a
     /// parameter that is not currently applicable.
+    ///
+    /// Variable-size parameters are returned as `SoundCoreParamValue::Bytes`.
     pub fn get(&self) -> windows::core::Result<SoundCoreParamValue> {
-        // varsize -> not supported
         if self.kind == 5 {
-            return Ok(SoundCoreParamValue::None);
+            return self.get_bytes();
         }
         unsafe {
             let param = Param {
@@ -115,6 +219,49 @@ impl SoundCoreParameter {
             Ok(convert_param_value(&value))
         }
     }
+    fn get_bytes(&self) -> windows::core::Result<SoundCoreParamValue> {
+        unsafe {
+            let span = trace_span!(
+                "Fetching variable-size parameter value...",
+                context = self.context,
+                feature_id = self.feature_id,
+                parameter_id = self.id,
+                size = tracing::field::Empty,
+            );
+            let _span = span.enter();
+            let mut data = vec![0u8; self.size.unwrap_or(0) as usize];
+            let size = loop {
+                let param = Param {
+                    context: self.context,
+                    feature: self.feature_id,
+                    param: self.id,
+                };
+                let mut size = data.len() as u32;
+                let result = self
+                    .core
+                    .GetParamValueEx(param, &mut size, data.as_mut_ptr())
+                    .ok();
+                // the value may have grown since the size was read, so the
+                // driver reports the size it needs instead
+                if size as usize > data.len() {
+                    trace!(size, "Buffer too small");
+                    data.resize(size as usize, 0);
+                    continue;
+                }
+                match result {
+                    Ok(()) => break size,
+                    Err(error) if error.code() == E_ACCESSDENIED => {
+                        trace!("Got ACCESSDENIED");
+                        return Ok(SoundCoreParamValue::None);
+                    }
+                    Err(error) => return Err(error),
+                }
+            };
+            span.record("size", size);
+            data.truncate(size as usize);
+            Ok(SoundCoreParamValue::Bytes(data))
+        }
+    }
     /// Sets the value of a parameter.
     ///
     /// May return `Err(Win32Error { code: E_ACCESSDENIED })` when setting a
@@ -126,6 +273,16 @@ impl SoundCoreParameter {
                 feature: self.feature_id,
                 param: self.id,
             };
+            if let SoundCoreParamValue::Bytes(data) = value {
+                info!(
+                    feature = self.feature_description, parameter = self.description, value = %value,
+                    "Setting value",
+                );
+                return self
+                    .core
+                    .SetParamValueEx(param, data.len() as u32, data.as_ptr())
+                    .ok();
+            }
             let param_value = ParamValue {
                 kind: match *value {
                     SoundCoreParamValue::Float(_) => 0,
@@ -0,0 +1,204 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::setting::Setting;
+
+/// Describes the volume level of a media endpoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Volume {
+    /// A volume level from 0.0 to 1.0
+    Scalar(f32),
+    /// A volume level in decibels, within the range supported by the device
+    Decibels(f32),
+}
+
+impl fmt::Display for Volume {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Volume::Scalar(v) => {
+                // round to avoid printing the noise from f32 -> percent conversion
+                let percent = (f64::from(v) * 10000.0).round() / 100.0;
+                write!(f, "{}%", percent)
+            }
+            Volume::Decibels(v) => write!(f, "{}dB", v),
+        }
+    }
+}
+
+impl FromStr for Volume {
+    type Err = ParseVolumeError;
+
+    /// Parses a volume level.
+    ///
+    /// Accepts scalars (`0.6`), percentages (`60%`), and decibels (`-12.5dB`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |number: &str| match f32::from_str(number.trim_end()) {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(ParseVolumeError {
+                input: s.to_owned(),
+            }),
+        };
+        let text = s.trim();
+        if let Some(number) = strip_suffix_ignore_case(text, "dB") {
+            Ok(Volume::Decibels(parse(number)?))
+        } else if let Some(number) = text.strip_suffix('%') {
+            Ok(Volume::Scalar(parse(number)? / 100.0))
+        } else {
+            Ok(Volume::Scalar(parse(text)?))
+        }
+    }
+}
+
+impl fmt::Display for Setting<Volume> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Setting::Absolute(volume) => volume.fmt(f),
+            // a leading - on decibels would be read back as an absolute level
+            Setting::Relative(Volume::Decibels(v)) if v < 0.0 => write!(f, "-={}dB", -v),
+            Setting::Relative(volume @ Volume::Scalar(v)) if v < 0.0 => volume.fmt(f),
+            Setting::Relative(volume) => write!(f, "+{}", volume),
+        }
+    }
+}
+
+impl FromStr for Setting<Volume> {
+    type Err = ParseVolumeError;
+
+    /// Parses a volume level or adjustment.
+    ///
+    /// Values starting with `+=`, `-=`, or `+` are relative adjustments.
+    /// Values starting with `-` are relative adjustments unless they are in
+    /// decibels, because absolute decibel levels are usually negative.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        if let Some(amount) = text.strip_prefix("+=") {
+            Ok(Setting::Relative(Volume::from_str(amount)?))
+        } else if let Some(amount) = text.strip_prefix("-=") {
+            Ok(Setting::Relative(Volume::from_str(amount)?.negate()))
+        } else if let Some(amount) = text.strip_prefix('+') {
+            Ok(Setting::Relative(Volume::from_str(amount)?))
+        } else {
+            match Volume::from_str(text)? {
+                volume @ Volume::Scalar(_) if text.starts_with('-') => {
+                    Ok(Setting::Relative(volume))
+                }
+                volume => Ok(Setting::Absolute(volume)),
+            }
+        }
+    }
+}
+
+impl Volume {
+    fn negate(self) -> Self {
+        match self {
+            Volume::Scalar(v) => Volume::Scalar(-v),
+            Volume::Decibels(v) => Volume::Decibels(-v),
+        }
+    }
+}
+
+fn strip_suffix_ignore_case<'a>(text: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = text.len().checked_sub(suffix.len())?;
+    if text.is_char_boundary(split) && text[split..].eq_ignore_ascii_case(suffix) {
+        Some(&text[..split])
+    } else {
+        None
+    }
+}
+
+/// Describes a failure to parse a volume level.
+#[derive(Debug)]
+pub struct ParseVolumeError {
+    input: String,
+}
+
+impl fmt::Display for ParseVolumeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid volume {:?}; expected a scalar (0.6), a percentage (60%), or decibels (-12.5dB)",
+            self.input
+        )
+    }
+}
+
+impl Error for ParseVolumeError {}
+
+/// Describes an attempt to set a volume level the device does not support.
+#[derive(Debug)]
+pub struct VolumeOutOfRangeError {
+    /// The requested volume level.
+    pub volume: Volume,
+    /// The minimum supported volume level.
+    pub min: Volume,
+    /// The maximum supported volume level.
+    pub max: Volume,
+}
+
+impl fmt::Display for VolumeOutOfRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "volume {} is out of range ({} to {})",
+            self.volume, self.min, self.max
+        )
+    }
+}
+
+impl Error for VolumeOutOfRangeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Setting<Volume> {
+        Setting::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!(Volume::from_str("0.6").unwrap(), Volume::Scalar(0.6));
+        assert_eq!(Volume::from_str("60%").unwrap(), Volume::Scalar(0.6));
+        assert_eq!(
+            Volume::from_str("-12.5dB").unwrap(),
+            Volume::Decibels(-12.5)
+        );
+        assert_eq!(
+            Volume::from_str("-12.5 db").unwrap(),
+            Volume::Decibels(-12.5)
+        );
+        assert!(Volume::from_str("loud").is_err());
+        assert!(Volume::from_str("infdB").is_err());
+    }
+
+    #[test]
+    fn plain_levels_are_absolute() {
+        assert_eq!(parse("60%"), Setting::Absolute(Volume::Scalar(0.6)));
+        assert_eq!(parse("-12dB"), Setting::Absolute(Volume::Decibels(-12.0)));
+    }
+
+    #[test]
+    fn signed_scalars_are_relative() {
+        assert_eq!(parse("+5%"), Setting::Relative(Volume::Scalar(0.05)));
+        assert_eq!(parse("-5%"), Setting::Relative(Volume::Scalar(-0.05)));
+        assert_eq!(parse("+3dB"), Setting::Relative(Volume::Decibels(3.0)));
+    }
+
+    #[test]
+    fn assignment_operators_are_relative() {
+        assert_eq!(parse("+=5%"), Setting::Relative(Volume::Scalar(0.05)));
+        assert_eq!(parse("-=5%"), Setting::Relative(Volume::Scalar(-0.05)));
+        assert_eq!(parse("-=3dB"), Setting::Relative(Volume::Decibels(-3.0)));
+        assert_eq!(parse("-=-3dB"), Setting::Relative(Volume::Decibels(3.0)));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in ["60%", "-12dB", "+5%", "-5%", "+3dB", "-=3dB"] {
+            let setting = parse(text);
+            assert_eq!(parse(&setting.to_string()), setting, "{}", text);
+        }
+        assert_eq!(parse("-=3dB").to_string(), "-=3dB");
+    }
+}